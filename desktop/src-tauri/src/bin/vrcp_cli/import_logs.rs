@@ -1,18 +1,30 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 use std::path::Path;
 
-use vrcp_lib::db::repositories::logs::LogsRepository;
 use vrcp_lib::db::DB;
-use vrcp_lib::modules::watcher::VrcLogEvent::{AppStart, AppStop, InvalidAppStop};
-use vrcp_lib::modules::watcher::{create_invalid_app_stop_payload, parse_log_line};
+use vrcp_lib::modules::backfill::{file_fingerprint, import_log_file};
+use vrcp_lib::modules::watcher::reload_custom_rules;
+use vrcp_lib::modules::watcher::UtcOffsetRanges;
+use vrcp_lib::utils::date::parse_utc_offset;
 
 /**
  * This program imports log files into the database.
  *
  * usage: cargo test -- --ignored import_logs <file_path...>
  */
-pub async fn import_logs(identifier: String, files: Vec<String>) {
+pub async fn import_logs(identifier: String, files: Vec<String>, timezone: Option<String>) {
+    // ログを書き出した PC のタイムゾーン (省略時はこの PC のローカル時間)
+    let utc_offset = match timezone.as_deref().map(parse_utc_offset) {
+        Some(None) => {
+            eprintln!(
+                "Invalid timezone: {:?} (expected \"+09:00\" or \"UTC\")",
+                timezone
+            );
+            return;
+        }
+        Some(offset) => offset,
+        None => None,
+    };
+
     let app_dir = dirs::data_local_dir()
         .expect("failed to resolve local data dir")
         .join(identifier);
@@ -20,7 +32,11 @@ pub async fn import_logs(identifier: String, files: Vec<String>) {
     // 2. データベース接続 (アプリと同じDBを開く)
     println!("Connecting to database...");
     let db = DB::new(app_dir).await.expect("failed to open database");
-    let log_repo = db.logs();
+
+    // アプリで登録したカスタムルールも適用する
+    if let Err(e) = reload_custom_rules(&db).await {
+        eprintln!("Failed to load custom rules: {}", e);
+    }
 
     // 3. ファイルごとの処理
     let mut total_imported = 0;
@@ -33,77 +49,43 @@ pub async fn import_logs(identifier: String, files: Vec<String>) {
         }
 
         println!("Processing: {:?}", path);
-        match process_file(path, &log_repo).await {
-            Ok((count, ecount)) => {
-                println!("  -> Imported {} lines. ({} skipped)", count, ecount);
-                total_imported += count;
-            }
-            Err(e) => eprintln!("  -> Error processing file: {}", e),
-        }
-    }
-
-    println!("Done! Total imported lines: {}", total_imported);
-}
-
-async fn process_file(
-    path: &Path,
-    log_repo: &LogsRepository,
-) -> Result<(i32, i32), Box<dyn std::error::Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut count = 0;
-    let mut ecount = 0;
-
-    let mut is_running = false;
-    let mut last_timestamp = 0i64;
-    // トランザクションを使うと高速ですが、今回はシンプルに1行ずつ処理
-    // 必要なら db.conn.lock().unwrap().transaction() ... を実装してください
-
-    for line_result in reader.lines() {
-        let line = line_result?;
-
-        // watcherのリファクタリングした関数を使用
-        if let Some(payload) = parse_log_line(&line) {
-            // チェックはDB側のUNIQUE制約(INSERT OR IGNORE等)や
-            // insert_logの実装に任せる (エラーが出ても止まらないようにする)
-            last_timestamp = payload.timestamp;
-            match &payload.event {
-                AppStart => {
-                    is_running = true;
-                }
-                AppStop | InvalidAppStop => {
-                    is_running = false;
+        // アプリ側と同じ処理 (不正な UTF-8 の置換、同秒イベントの区別、クラッシュ検出) で取り込む
+        match import_log_file(&db, path, utc_offset, UtcOffsetRanges::default()).await {
+            Ok(result) => {
+                if result.malformed_lines > 0 {
+                    println!(
+                        "  -> Warning: {} lines contained invalid UTF-8.",
+                        result.malformed_lines
+                    );
                 }
-                _ => {}
-            }
-            match log_repo.insert_log(&payload).await {
-                Ok(count_inserted) => {
-                    count += count_inserted;
-                    if count_inserted == 0 {
-                        ecount += 1; // 重複等で挿入されなかった場合をエラーとしてカウント
-                    }
-                }
-                Err(e) => {
-                    eprintln!("\tinsert error: {}", e);
+                if result.ended_mid_session {
+                    println!("  -> Warning: Log ended while app was still running. (inserted InvalidAppStop)");
                 }
-            }
-        }
-    }
-    if is_running {
-        println!("  -> Warning: Log ended while app was still running. (inserted InvalidAppStop)");
-        let crash_payload = create_invalid_app_stop_payload(last_timestamp);
-        match log_repo.insert_log(&crash_payload).await {
-            Ok(count_inserted) => {
-                count += count_inserted;
-                if count_inserted == 0 {
-                    ecount += 1; // 重複等で挿入されなかった場合をエラーとしてカウント
+                println!(
+                    "  -> Imported {} lines. ({} skipped)",
+                    result.inserted, result.skipped
+                );
+                total_imported += result.inserted;
+
+                // アプリのバックフィルで再度取り込まないよう記録する
+                if let Ok((size, content_hash)) = file_fingerprint(path) {
+                    let abs_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+                    let path_str = abs_path.to_string_lossy();
+                    let _ = db
+                        .imported_files()
+                        .mark_imported(
+                            &path_str,
+                            size,
+                            content_hash,
+                            result.utc_offset,
+                            result.utc_offsets,
+                        )
+                        .await;
                 }
             }
-            Err(e) => {
-                eprintln!("\tinsert error: {}", e);
-            }
+            Err(e) => eprintln!("  -> Error processing file: {}", e),
         }
     }
 
-    Ok((count, ecount))
+    println!("Done! Total imported lines: {}", total_imported);
 }
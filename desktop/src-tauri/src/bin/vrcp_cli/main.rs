@@ -1,6 +1,7 @@
 use clap::{Parser, Subcommand};
 mod gen_bindings;
 mod import_logs;
+mod reparse_logs;
 // cargo run --bin vrcp_cli -- <SUBCOMMAND>
 
 #[derive(Parser)]
@@ -22,6 +23,14 @@ enum Commands {
         identifier: String, // --identifier=<identifier>
         #[arg(required = true, num_args = 1..)]
         files: Vec<String>,
+        /// ログを書き出した PC の UTC オフセット ("+09:00" / "UTC")
+        #[arg(long)]
+        timezone: Option<String>, // --timezone=<offset>
+    },
+    /// 保存済みのログを現在のルールで再解析
+    ReparseLogs {
+        #[arg(long, default_value = "cc.amgr.vrcp.desktop.dev")]
+        identifier: String, // --identifier=<identifier>
     },
 }
 
@@ -35,8 +44,16 @@ async fn main() -> anyhow::Result<()> {
             gen_bindings::gen_bindings();
         }
 
-        Commands::ImportLogs { identifier, files } => {
-            import_logs::import_logs(identifier, files).await;
+        Commands::ImportLogs {
+            identifier,
+            files,
+            timezone,
+        } => {
+            import_logs::import_logs(identifier, files, timezone).await;
+        }
+
+        Commands::ReparseLogs { identifier } => {
+            reparse_logs::reparse_logs(identifier).await;
         }
     }
     Ok(())
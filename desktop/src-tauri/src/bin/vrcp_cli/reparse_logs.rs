@@ -0,0 +1,40 @@
+use vrcp_lib::db::DB;
+use vrcp_lib::modules::backfill::{get_parser_version, run_reparse};
+use vrcp_lib::modules::watcher::reload_custom_rules;
+
+/**
+ * Re-parses archived logs and the logs still left in the VRChat log directory
+ * with the current rules, inserting only events that are not in the database yet.
+ */
+pub async fn reparse_logs(identifier: String) {
+    let app_dir = dirs::data_local_dir()
+        .expect("failed to resolve local data dir")
+        .join(identifier);
+
+    println!("Connecting to database...");
+    let db = DB::new(app_dir.clone())
+        .await
+        .expect("failed to open database");
+
+    // アプリで登録したカスタムルールも適用する
+    if let Err(e) = reload_custom_rules(&db).await {
+        eprintln!("Failed to load custom rules: {}", e);
+    }
+
+    if let Ok(version) = get_parser_version(&db).await {
+        println!(
+            "Parser version: {} (stored data: {})",
+            version.current, version.stored
+        );
+    }
+
+    // アプリと同じアーカイブの保存先 (app_local_data_dir/log_archive)
+    let archive_dir = app_dir.join("log_archive");
+    match run_reparse(&db, Some(&archive_dir)).await {
+        Ok(result) => println!(
+            "Done! {} files, {} new events ({} already stored)",
+            result.files, result.inserted, result.skipped
+        ),
+        Err(e) => eprintln!("Reparse failed: {}", e),
+    }
+}
@@ -1,6 +1,8 @@
 use std::fs::File;
 use std::io::BufWriter;
 
+use crate::modules::archiver;
+use crate::modules::backfill::{self, ParserVersion, ReparseResult};
 use crate::modules::watcher::LogPayload;
 use crate::Ctx;
 
@@ -29,6 +31,12 @@ pub async fn delete_all_logs(state: tauri::State<'_, Ctx>) -> Result<(), String>
         .delete_all_logs()
         .await
         .map_err(|e| e.to_string())?;
+    state
+        .db
+        .imported_files()
+        .delete_all()
+        .await
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -53,3 +61,44 @@ pub async fn export_logs(state: tauri::State<'_, Ctx>, file_path: String) -> Res
 
     Ok(count)
 }
+
+/// ログディレクトリ内の未取り込みのファイルをバックグラウンドで取り込む
+/// (進捗は BackfillProgress イベントで通知)
+#[tauri::command]
+#[specta::specta]
+pub async fn start_backfill(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Ctx>,
+) -> Result<(), String> {
+    if backfill::is_backfill_running() {
+        return Err("Backfill is already running".to_string());
+    }
+    let db = state.db.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = backfill::run_backfill(&app, &db).await {
+            eprintln!("Backfill failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// 現在のパーサーと保存済みデータのパーサーのバージョン
+/// (stored < current ならアップデートで新しいイベントを認識できるようになっている)
+#[tauri::command]
+#[specta::specta]
+pub async fn get_parser_version(state: tauri::State<'_, Ctx>) -> Result<ParserVersion, String> {
+    backfill::get_parser_version(&state.db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// アーカイブ済み・ログディレクトリに残っているログを現在のルールで解析し直す
+#[tauri::command]
+#[specta::specta]
+pub async fn reparse_logs(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Ctx>,
+) -> Result<ReparseResult, String> {
+    let archive_dir = archiver::archive_dir(&app);
+    backfill::run_reparse(&state.db, archive_dir.as_deref()).await
+}
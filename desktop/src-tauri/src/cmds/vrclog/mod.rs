@@ -1,3 +1,7 @@
 // 各種サービス(ビジネスロジック, tauricmds)
 pub mod logs;
+pub mod rules;
+pub mod screenshots;
 pub mod sessions;
+pub mod settings;
+pub mod watcher;
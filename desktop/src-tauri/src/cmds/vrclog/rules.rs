@@ -0,0 +1,61 @@
+use crate::db::repositories::custom_rules::CustomRule;
+use crate::modules::watcher::{self, compile_custom_rule, reload_custom_rules, CustomRuleTest};
+use crate::Ctx;
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_custom_rules(state: tauri::State<'_, Ctx>) -> Result<Vec<CustomRule>, String> {
+    state
+        .db
+        .custom_rules()
+        .get_rules()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn add_custom_rule(
+    state: tauri::State<'_, Ctx>,
+    name: String,
+    pattern: String,
+) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Rule name must not be empty".to_string());
+    }
+    // 保存前にコンパイルできるか確認
+    compile_custom_rule(&pattern)?;
+
+    state
+        .db
+        .custom_rules()
+        .save_rule(name.trim(), &pattern)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // watcher は毎行マッチャーを参照するので、差し替えれば再起動不要
+    reload_custom_rules(&state.db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn test_custom_rule(pattern: String, line: String) -> Result<CustomRuleTest, String> {
+    watcher::test_custom_rule(&pattern, &line)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_custom_rule(state: tauri::State<'_, Ctx>, id: i32) -> Result<(), String> {
+    state
+        .db
+        .custom_rules()
+        .delete_rule(id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    reload_custom_rules(&state.db)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,53 @@
+use crate::db::repositories::screenshots::Screenshot;
+use crate::modules::screenshots;
+use crate::Ctx;
+
+/// セッションの期間 (SessionPayload の startTime / endTime) に撮ったスクリーンショット
+#[tauri::command]
+#[specta::specta]
+pub async fn get_session_screenshots(
+    state: tauri::State<'_, Ctx>,
+    start: i64,
+    end: i64,
+) -> Result<Vec<Screenshot>, String> {
+    state
+        .db
+        .screenshots()
+        .get_screenshots(start, end)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 保存先のフォルダを走査し直す。新しく見つかった件数を返す
+#[tauri::command]
+#[specta::specta]
+pub async fn scan_screenshots(state: tauri::State<'_, Ctx>) -> Result<u32, String> {
+    screenshots::scan_screenshots(&state.db).await
+}
+
+/// メタデータの無いスクリーンショットに、セッションから復元した情報を書き込むか
+#[tauri::command]
+#[specta::specta]
+pub async fn get_screenshot_stamping(state: tauri::State<'_, Ctx>) -> Result<bool, String> {
+    state
+        .db
+        .settings()
+        .get_screenshot_stamping()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 有効にすると、以降に撮ったものと、次の scan_screenshots で見つかったものに書き込む
+#[tauri::command]
+#[specta::specta]
+pub async fn set_screenshot_stamping(
+    state: tauri::State<'_, Ctx>,
+    enabled: bool,
+) -> Result<(), String> {
+    state
+        .db
+        .settings()
+        .set_screenshot_stamping(enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,4 +1,7 @@
+use crate::db::repositories::process_samples::ProcessSample;
+use crate::db::repositories::screenshots::Screenshot;
 use crate::modules::watcher::{LogPayload, VrcLogEvent};
+use crate::utils::instance::InstanceInfo;
 use crate::Ctx;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -14,12 +17,42 @@ pub struct Interval {
     pub end: i64,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, Type)]
+pub struct AvatarUsage {
+    #[serde(rename = "avatarName")]
+    pub avatar_name: String,
+    pub timestamp: i64,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Type)]
 pub struct PlayerInterval {
     pub name: String,
     pub intervals: Vec<Interval>,
     #[serde(rename = "totalDurationMs")]
     pub total_duration_ms: i64,
+    pub avatars: Vec<AvatarUsage>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Type)]
+pub struct VideoPlayback {
+    pub url: String,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: Option<String>,
+    pub timestamp: i64,
+}
+
+/// セッション中の VRChat のリソース使用量 (process_samples の集計)
+#[derive(Clone, Serialize, Deserialize, Debug, Type)]
+pub struct ResourceUsage {
+    #[serde(rename = "peakMemoryBytes")]
+    pub peak_memory_bytes: i64,
+    /// 1コアを 100 とした CPU 使用率の平均
+    #[serde(rename = "avgCpuUsage")]
+    pub avg_cpu_usage: f64,
+    #[serde(rename = "peakThreadCount")]
+    pub peak_thread_count: Option<i32>,
+    #[serde(rename = "sampleCount")]
+    pub sample_count: u32,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, Type)]
@@ -28,6 +61,7 @@ pub struct SessionPayload {
     pub world_name: String,
     #[serde(rename = "instanceId")]
     pub instance_id: String,
+    pub instance: Option<InstanceInfo>,
     #[serde(rename = "startTime")]
     pub start_time: i64,
     #[serde(rename = "endTime")]
@@ -35,7 +69,16 @@ pub struct SessionPayload {
     #[serde(rename = "durationMs")]
     pub duration_ms: i64,
     pub username: Option<String>,
+    /// ログインしていたアカウントの user_id
+    pub account: Option<String>,
+    #[serde(rename = "localPlayer")]
+    pub local_player: Option<PlayerInterval>,
     pub players: Vec<PlayerInterval>,
+    pub videos: Vec<VideoPlayback>,
+    /// プロセスを監視していなかった期間のセッションは None
+    pub resources: Option<ResourceUsage>,
+    /// セッション中に撮ったスクリーンショット (古い順)
+    pub screenshots: Vec<Screenshot>,
 }
 
 // ================================================================
@@ -70,6 +113,10 @@ struct SessionBuilder {
     active_players: HashMap<String, ActivePlayer>,
     player_intervals: HashMap<String, Vec<Interval>>,
     known_player_names: HashMap<String, String>,
+    player_avatars: HashMap<String, Vec<AvatarUsage>>,
+    // セッションを跨いでも自分のアバターは引き継がれるため、最後に着ていたものを保持
+    last_local_avatar: Option<String>,
+    videos: Vec<VideoPlayback>,
 }
 
 impl SessionBuilder {
@@ -82,6 +129,9 @@ impl SessionBuilder {
             active_players: HashMap::new(),
             player_intervals: HashMap::new(),
             known_player_names: HashMap::new(),
+            player_avatars: HashMap::new(),
+            last_local_avatar: None,
+            videos: Vec::new(),
         }
     }
 
@@ -101,6 +151,7 @@ impl SessionBuilder {
 
             // 2. プレイヤーデータを整形
             let mut players: Vec<PlayerInterval> = Vec::new();
+            let mut local_player: Option<PlayerInterval> = None;
 
             for (id, intervals) in self.player_intervals.drain() {
                 let name = self
@@ -110,6 +161,7 @@ impl SessionBuilder {
                     .unwrap_or_else(|| "Unknown".to_string());
 
                 let total_ms: i64 = intervals.iter().map(|i| i.end - i.start).sum();
+                let avatars = self.player_avatars.remove(&id).unwrap_or_default();
 
                 // 自分のIDかどうか判定
                 let is_me = if let Some(me_ref) = &self.me {
@@ -125,14 +177,22 @@ impl SessionBuilder {
                     if let Some(last) = intervals.last() {
                         final_end_time = last.end;
                     }
+                    local_player = Some(PlayerInterval {
+                        name,
+                        intervals,
+                        total_duration_ms: total_ms,
+                        avatars,
+                    });
                 } else {
                     players.push(PlayerInterval {
                         name,
                         intervals,
                         total_duration_ms: total_ms,
+                        avatars,
                     });
                 }
             }
+            self.player_avatars.clear();
 
             // ソート
             players.sort_by(|a, b| b.total_duration_ms.cmp(&a.total_duration_ms));
@@ -141,16 +201,65 @@ impl SessionBuilder {
 
             self.sessions.push(SessionPayload {
                 world_name: session_state.world_name,
+                instance: InstanceInfo::parse(&session_state.instance_id),
                 instance_id: session_state.instance_id,
                 start_time: session_state.start_time,
                 end_time: final_end_time,
                 duration_ms,
                 username: self.me.as_ref().map(|m| m.name.clone()),
+                account: self.me.as_ref().map(|m| m.user_id.clone()),
+                local_player,
                 players,
+                videos: std::mem::take(&mut self.videos),
+                resources: None,
+                screenshots: Vec::new(),
             });
         }
     }
 
+    // ログには表示名しか出ないため、自分または在室中のプレイヤーから user_id を引く
+    fn record_avatar(&mut self, player_name: String, avatar_name: String, timestamp: i64) {
+        let user_id = match &self.me {
+            Some(me) if me.name == player_name => {
+                self.last_local_avatar = Some(avatar_name.clone());
+                Some(me.user_id.clone())
+            }
+            _ => self
+                .active_players
+                .keys()
+                .find(|id| self.known_player_names.get(*id) == Some(&player_name))
+                .cloned(),
+        };
+
+        if let Some(id) = user_id {
+            let avatars = self.player_avatars.entry(id).or_default();
+            if avatars.last().map(|a| &a.avatar_name) != Some(&avatar_name) {
+                avatars.push(AvatarUsage {
+                    avatar_name,
+                    timestamp,
+                });
+            }
+        }
+    }
+
+    // 同じ動画は複数のログ行 ([Video Playback] / [USharpVideo] 等) に出るため、
+    // 直近の同一URLはまとめて requested_by だけ補完する
+    fn push_video(&mut self, url: String, requested_by: Option<String>, timestamp: i64) {
+        if let Some(last) = self.videos.last_mut() {
+            if last.url == url && timestamp - last.timestamp <= 5000 {
+                if last.requested_by.is_none() {
+                    last.requested_by = requested_by;
+                }
+                return;
+            }
+        }
+        self.videos.push(VideoPlayback {
+            url,
+            requested_by,
+            timestamp,
+        });
+    }
+
     // メインの処理ループ
     fn process(mut self, logs: Vec<LogPayload>, last_logged_time: i64) -> Vec<SessionPayload> {
         for log in logs {
@@ -179,6 +288,17 @@ impl SessionBuilder {
                         instance_id,
                         start_time: log.timestamp,
                     });
+
+                    // 前のセッションから着ているアバターを引き継ぐ
+                    if let (Some(me), Some(avatar)) = (&self.me, &self.last_local_avatar) {
+                        self.player_avatars.insert(
+                            me.user_id.clone(),
+                            vec![AvatarUsage {
+                                avatar_name: avatar.clone(),
+                                timestamp: log.timestamp,
+                            }],
+                        );
+                    }
                 }
                 VrcLogEvent::PlayerJoin {
                     player_name,
@@ -208,6 +328,21 @@ impl SessionBuilder {
                         }
                     }
                 }
+                VrcLogEvent::AvatarChange {
+                    player_name,
+                    avatar_name,
+                } => {
+                    if self.current_session.is_some() {
+                        self.record_avatar(player_name, avatar_name, log.timestamp);
+                    } else if self.me.as_ref().is_some_and(|me| me.name == player_name) {
+                        self.last_local_avatar = Some(avatar_name);
+                    }
+                }
+                VrcLogEvent::VideoPlay { url, requested_by } => {
+                    if self.current_session.is_some() {
+                        self.push_video(url, requested_by, log.timestamp);
+                    }
+                }
                 VrcLogEvent::AppStop | VrcLogEvent::InvalidAppStop => {
                     if self.current_session.is_some() {
                         self.close_session(log.timestamp);
@@ -237,8 +372,6 @@ pub async fn get_sessions(
     start: Option<i64>,
     end: Option<i64>,
 ) -> Result<Vec<SessionPayload>, String> {
-    let builder = SessionBuilder::new();
-
     let logs = state
         .db
         .logs()
@@ -249,5 +382,280 @@ pub async fn get_sessions(
     // WatcherState から「最後に書き込まれたログの時間」を取得
     let last_logged_time = state.watcher.last_seen_timestamp();
 
-    Ok(builder.process(logs, last_logged_time))
+    let mut sessions = build_sessions(logs, last_logged_time);
+
+    if let (Some(first), Some(last)) = (
+        sessions.iter().map(|s| s.start_time).min(),
+        sessions.iter().map(|s| s.end_time).max(),
+    ) {
+        let samples = state
+            .db
+            .process_samples()
+            .get_samples(first, last)
+            .await
+            .map_err(|e| e.to_string())?;
+        attach_resource_usage(&mut sessions, &samples);
+
+        let screenshots = state
+            .db
+            .screenshots()
+            .get_screenshots(first, last)
+            .await
+            .map_err(|e| e.to_string())?;
+        attach_screenshots(&mut sessions, &screenshots);
+    }
+
+    Ok(sessions)
+}
+
+/// screenshots は古い順。撮影時刻がセッションの期間に含まれるものを紐付ける。
+/// 複数クライアントを同時に起動していた期間は、セッションと同じアカウントで撮ったものだけにする
+/// (アカウントが分からない古いスクリーンショットは時刻だけで紐付ける)
+fn attach_screenshots(sessions: &mut [SessionPayload], screenshots: &[Screenshot]) {
+    for session in sessions.iter_mut() {
+        let from = screenshots.partition_point(|s| s.taken_at < session.start_time);
+        let to = screenshots.partition_point(|s| s.taken_at <= session.end_time);
+        session.screenshots = screenshots[from..to.max(from)]
+            .iter()
+            .filter(|s| s.account.is_none() || s.account == session.account)
+            .cloned()
+            .collect();
+    }
+}
+
+/// samples は古い順。複数クライアントを同時に起動していた期間は、
+/// セッションと同じアカウントのログを書いていたプロセスの分だけを集計する
+fn attach_resource_usage(sessions: &mut [SessionPayload], samples: &[ProcessSample]) {
+    for session in sessions.iter_mut() {
+        let from = samples.partition_point(|s| s.timestamp < session.start_time);
+        let to = samples.partition_point(|s| s.timestamp <= session.end_time);
+        let in_session: Vec<&ProcessSample> = samples[from..to.max(from)]
+            .iter()
+            .filter(|s| s.account.is_some() && s.account == session.account)
+            .collect();
+        if in_session.is_empty() {
+            continue;
+        }
+
+        session.resources = Some(ResourceUsage {
+            peak_memory_bytes: in_session.iter().map(|s| s.memory_bytes).max().unwrap_or(0),
+            avg_cpu_usage: in_session.iter().map(|s| s.cpu_usage).sum::<f64>()
+                / in_session.len() as f64,
+            peak_thread_count: in_session.iter().filter_map(|s| s.thread_count).max(),
+            sample_count: in_session.len() as u32,
+        });
+    }
+}
+
+/// 複数クライアントを同時に起動していた場合にログが混ざらないよう、
+/// アカウントごとにセッションを組み立ててから開始時刻順に並べる
+pub(crate) fn build_sessions(logs: Vec<LogPayload>, last_logged_time: i64) -> Vec<SessionPayload> {
+    let mut by_account: HashMap<Option<String>, Vec<LogPayload>> = HashMap::new();
+    for log in logs {
+        by_account.entry(log.account.clone()).or_default().push(log);
+    }
+
+    let mut sessions: Vec<SessionPayload> = by_account
+        .into_values()
+        .flat_map(|logs| SessionBuilder::new().process(logs, last_logged_time))
+        .collect();
+    sessions.sort_by_key(|s| s.start_time);
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(account: &str, start_time: i64, end_time: i64) -> SessionPayload {
+        SessionPayload {
+            world_name: "Test World".to_string(),
+            instance_id: "wrld_test:12345".to_string(),
+            instance: None,
+            start_time,
+            end_time,
+            duration_ms: end_time - start_time,
+            username: None,
+            account: Some(account.to_string()),
+            local_player: None,
+            players: Vec::new(),
+            videos: Vec::new(),
+            resources: None,
+            screenshots: Vec::new(),
+        }
+    }
+
+    fn log(timestamp: i64, event: VrcLogEvent) -> LogPayload {
+        LogPayload {
+            event,
+            timestamp,
+            hash: timestamp,
+            instance: None,
+            account: Some("usr_me".to_string()),
+        }
+    }
+
+    fn join(timestamp: i64, instance: &str) -> LogPayload {
+        log(
+            timestamp,
+            VrcLogEvent::InstanceJoin {
+                world_id: "wrld_test".to_string(),
+                instance_id: format!("wrld_test:{}", instance),
+            },
+        )
+    }
+
+    fn player_join(timestamp: i64, name: &str, user_id: &str) -> LogPayload {
+        log(
+            timestamp,
+            VrcLogEvent::PlayerJoin {
+                player_name: name.to_string(),
+                user_id: user_id.to_string(),
+            },
+        )
+    }
+
+    fn switch(timestamp: i64, name: &str, avatar: &str) -> LogPayload {
+        log(
+            timestamp,
+            VrcLogEvent::AvatarChange {
+                player_name: name.to_string(),
+                avatar_name: avatar.to_string(),
+            },
+        )
+    }
+
+    fn avatar_names(player: &PlayerInterval) -> Vec<&str> {
+        player
+            .avatars
+            .iter()
+            .map(|a| a.avatar_name.as_str())
+            .collect()
+    }
+
+    fn sample(pid: i32, timestamp: i64, memory_bytes: i64, account: Option<&str>) -> ProcessSample {
+        ProcessSample {
+            id: 0,
+            pid,
+            timestamp,
+            cpu_usage: pid as f64,
+            memory_bytes,
+            thread_count: None,
+            account: account.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn counts_only_the_samples_of_the_session_account() {
+        let mut sessions = [session("usr_main", 0, 100), session("usr_alt", 0, 100)];
+        let samples = [
+            sample(1, 10, 1_000, Some("usr_main")),
+            sample(2, 10, 5_000, Some("usr_alt")),
+            sample(3, 15, 9_000, None),
+            sample(1, 20, 2_000, Some("usr_main")),
+            sample(2, 20, 6_000, Some("usr_alt")),
+        ];
+        attach_resource_usage(&mut sessions, &samples);
+
+        let main = sessions[0].resources.as_ref().unwrap();
+        assert_eq!(main.sample_count, 2);
+        assert_eq!(main.peak_memory_bytes, 2_000);
+        assert_eq!(main.avg_cpu_usage, 1.0);
+
+        let alt = sessions[1].resources.as_ref().unwrap();
+        assert_eq!(alt.sample_count, 2);
+        assert_eq!(alt.peak_memory_bytes, 6_000);
+        assert_eq!(alt.avg_cpu_usage, 2.0);
+    }
+
+    fn screenshot(id: i32, taken_at: i64, account: Option<&str>) -> Screenshot {
+        Screenshot {
+            id,
+            file_name: format!("VRChat_{}.png", id),
+            path: format!("/screenshots/VRChat_{}.png", id),
+            taken_at,
+            account: account.map(str::to_string),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn attaches_only_the_screenshots_of_the_session_account() {
+        let mut sessions = [session("usr_main", 0, 100), session("usr_alt", 0, 100)];
+        let screenshots = [
+            screenshot(1, 10, Some("usr_main")),
+            screenshot(2, 20, Some("usr_alt")),
+            screenshot(3, 30, None),
+            screenshot(4, 200, Some("usr_main")),
+        ];
+        attach_screenshots(&mut sessions, &screenshots);
+
+        let ids = |session: &SessionPayload| -> Vec<i32> {
+            session.screenshots.iter().map(|s| s.id).collect()
+        };
+        // アカウントが分からない古いスクリーンショットは両方に付く
+        assert_eq!(ids(&sessions[0]), vec![1, 3]);
+        assert_eq!(ids(&sessions[1]), vec![2, 3]);
+    }
+
+    #[test]
+    fn attributes_avatar_switches_to_the_player_by_display_name() {
+        let logs = vec![
+            log(
+                0,
+                VrcLogEvent::Login {
+                    username: "Me".to_string(),
+                    user_id: "usr_me".to_string(),
+                },
+            ),
+            join(1_000, "1"),
+            player_join(1_000, "Me", "usr_me"),
+            player_join(2_000, "Friend", "usr_friend"),
+            switch(3_000, "Friend", "Robot"),
+            // 同じアバターが続けて出ても1回とみなす
+            switch(3_500, "Friend", "Robot"),
+            switch(4_000, "Me", "Cat"),
+            // 在室していないプレイヤーの分は誰にも付けない
+            switch(5_000, "Stranger", "Ghost"),
+            switch(6_000, "Friend", "Robot v2"),
+        ];
+        let sessions = SessionBuilder::new().process(logs, 10_000);
+        assert_eq!(sessions.len(), 1);
+
+        let me = sessions[0].local_player.as_ref().unwrap();
+        assert_eq!(avatar_names(me), ["Cat"]);
+        assert_eq!(sessions[0].players.len(), 1);
+        let friend = &sessions[0].players[0];
+        assert_eq!(friend.name, "Friend");
+        assert_eq!(avatar_names(friend), ["Robot", "Robot v2"]);
+        assert_eq!(friend.avatars[0].timestamp, 3_000);
+    }
+
+    #[test]
+    fn carries_the_local_avatar_into_the_next_session() {
+        let logs = vec![
+            log(
+                0,
+                VrcLogEvent::Login {
+                    username: "Me".to_string(),
+                    user_id: "usr_me".to_string(),
+                },
+            ),
+            // ワールドに入る前 (ホーム読み込み中など) の着替え
+            switch(500, "Me", "Cat"),
+            join(1_000, "1"),
+            player_join(1_000, "Me", "usr_me"),
+            join(5_000, "2"),
+            player_join(5_000, "Me", "usr_me"),
+            switch(6_000, "Me", "Dog"),
+        ];
+        let sessions = SessionBuilder::new().process(logs, 10_000);
+        assert_eq!(sessions.len(), 2);
+
+        let first = sessions[0].local_player.as_ref().unwrap();
+        assert_eq!(avatar_names(first), ["Cat"]);
+        let second = sessions[1].local_player.as_ref().unwrap();
+        assert_eq!(avatar_names(second), ["Cat", "Dog"]);
+        assert_eq!(second.avatars[0].timestamp, 5_000);
+    }
 }
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use crate::db::repositories::settings::ArchiveRetention;
+use crate::modules::archiver;
+use crate::modules::watcher::get_vrc_log_dir;
+use crate::utils::logdir;
+use crate::Ctx;
+
+/// 現在使用中のログディレクトリ
+#[tauri::command]
+#[specta::specta]
+pub async fn get_log_dir(state: tauri::State<'_, Ctx>) -> Result<Option<String>, String> {
+    Ok(get_vrc_log_dir(&state.db)
+        .await
+        .map(|p| p.to_string_lossy().to_string()))
+}
+
+/// ログディレクトリを上書き設定する (None で自動検出に戻す)
+#[tauri::command]
+#[specta::specta]
+pub async fn set_log_dir(state: tauri::State<'_, Ctx>, dir: Option<String>) -> Result<(), String> {
+    if let Some(d) = &dir {
+        if !PathBuf::from(d).is_dir() {
+            return Err(format!("Directory not found: {}", d));
+        }
+    }
+    state
+        .db
+        .settings()
+        .set_log_dir(dir.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 自動検出したログディレクトリの候補一覧
+#[tauri::command]
+#[specta::specta]
+pub async fn list_log_dir_candidates() -> Result<Vec<String>, String> {
+    Ok(logdir::find_log_dir_candidates()
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// ログアーカイブの保持設定
+#[tauri::command]
+#[specta::specta]
+pub async fn get_archive_retention(
+    state: tauri::State<'_, Ctx>,
+) -> Result<ArchiveRetention, String> {
+    state
+        .db
+        .settings()
+        .get_archive_retention()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 保持設定を保存し、すぐに古いアーカイブを整理する
+#[tauri::command]
+#[specta::specta]
+pub async fn set_archive_retention(
+    state: tauri::State<'_, Ctx>,
+    retention: ArchiveRetention,
+) -> Result<(), String> {
+    state
+        .db
+        .settings()
+        .set_archive_retention(&retention)
+        .await
+        .map_err(|e| e.to_string())?;
+    archiver::apply_retention(&state.db).await?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use crate::modules::watcher::{WatcherCommand, WatcherStatus};
+use crate::Ctx;
+
+/// watcher の現在の状態 (追跡中のファイル・読み込み位置・遅延など)
+#[tauri::command]
+#[specta::specta]
+pub async fn get_watcher_status(state: tauri::State<'_, Ctx>) -> Result<WatcherStatus, String> {
+    Ok(state.watcher.status())
+}
+
+/// ログの読み込みを一時停止する
+#[tauri::command]
+#[specta::specta]
+pub async fn pause_watcher(state: tauri::State<'_, Ctx>) -> Result<(), String> {
+    state.watcher.send(WatcherCommand::Pause)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn resume_watcher(state: tauri::State<'_, Ctx>) -> Result<(), String> {
+    state.watcher.send(WatcherCommand::Resume)
+}
+
+/// 現在のファイルを先頭から読み直す
+#[tauri::command]
+#[specta::specta]
+pub async fn rescan_watcher(state: tauri::State<'_, Ctx>) -> Result<(), String> {
+    state.watcher.send(WatcherCommand::Rescan)
+}
+
+/// 指定したログファイルだけを先頭から追跡する (None で最新のログの自動追跡に戻す)
+#[tauri::command]
+#[specta::specta]
+pub async fn switch_watcher_file(
+    state: tauri::State<'_, Ctx>,
+    path: Option<String>,
+) -> Result<(), String> {
+    let path = path.map(PathBuf::from);
+    if let Some(p) = &path {
+        if !p.is_file() {
+            return Err(format!("File not found: {}", p.display()));
+        }
+    }
+    state.watcher.send(WatcherCommand::SwitchTo(path))
+}
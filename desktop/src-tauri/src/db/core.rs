@@ -1,4 +1,9 @@
-use super::repositories::{logs::LogsRepository, settings::SettingsRepository};
+use super::repositories::{
+    archived_logs::ArchivedLogsRepository, custom_rules::CustomRulesRepository,
+    imported_files::ImportedFilesRepository, logs::LogsRepository,
+    process_samples::ProcessSamplesRepository, screenshots::ScreenshotsRepository,
+    settings::SettingsRepository, watcher_states::WatcherStatesRepository,
+};
 use crate::db::migrator::Migrator;
 use sea_orm::{ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbErr, Statement};
 use sea_orm_migration::MigratorTrait;
@@ -49,4 +54,28 @@ impl DB {
     pub fn settings(&self) -> SettingsRepository {
         SettingsRepository::new(self.connection.clone())
     }
+
+    pub fn custom_rules(&self) -> CustomRulesRepository {
+        CustomRulesRepository::new(self.connection.clone())
+    }
+
+    pub fn watcher_states(&self) -> WatcherStatesRepository {
+        WatcherStatesRepository::new(self.connection.clone())
+    }
+
+    pub fn imported_files(&self) -> ImportedFilesRepository {
+        ImportedFilesRepository::new(self.connection.clone())
+    }
+
+    pub fn archived_logs(&self) -> ArchivedLogsRepository {
+        ArchivedLogsRepository::new(self.connection.clone())
+    }
+
+    pub fn process_samples(&self) -> ProcessSamplesRepository {
+        ProcessSamplesRepository::new(self.connection.clone())
+    }
+
+    pub fn screenshots(&self) -> ScreenshotsRepository {
+        ScreenshotsRepository::new(self.connection.clone())
+    }
 }
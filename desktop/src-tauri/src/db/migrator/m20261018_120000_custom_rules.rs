@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Custom Rules Table (user-defined log matching rules)
+        manager
+            .create_table(
+                Table::create()
+                    .table(CustomRules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CustomRules::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CustomRules::Name)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(CustomRules::Pattern).string().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CustomRules::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum CustomRules {
+    Table,
+    Id,
+    Name,
+    Pattern,
+}
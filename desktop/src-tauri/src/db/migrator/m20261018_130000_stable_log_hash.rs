@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement, TransactionTrait};
+
+use crate::utils::hash::event_hash_v2;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 1回の UPDATE で書き換える行数
+const BATCH_SIZE: u64 = 500;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        // SQLite ではマイグレーションがトランザクションで囲まれないため、列の追加と再計算を
+        // 1トランザクションにまとめる。マイグレーションの記録はこの後に別で書かれ、
+        // その前に落ちると次回もう一度実行されるので、列が既にあれば追加は飛ばす
+        let has_column = manager.has_column("logs", "hash_version").await?;
+        let txn = db.begin().await?;
+        if !has_column {
+            // 既存行は DefaultHasher (v1) で計算されている
+            txn.execute(
+                backend.build(
+                    &Table::alter()
+                        .table(Logs::Table)
+                        .add_column(
+                            ColumnDef::new(Logs::HashVersion)
+                                .integer()
+                                .not_null()
+                                .default(1),
+                        )
+                        .to_owned(),
+                ),
+            )
+            .await?;
+        }
+
+        // 保存済みの data / timestamp から v2 ハッシュを再計算
+        let mut last_id: i32 = 0;
+        loop {
+            let rows = txn
+                .query_all(Statement::from_sql_and_values(
+                    backend,
+                    "SELECT id, timestamp, data FROM logs WHERE hash_version = 1 AND id > ? ORDER BY id LIMIT ?",
+                    [last_id.into(), BATCH_SIZE.into()],
+                ))
+                .await?;
+            if rows.is_empty() {
+                break;
+            }
+
+            let mut cases = String::new();
+            let mut values: Vec<Value> = Vec::with_capacity(rows.len() * 3);
+            let mut ids: Vec<Value> = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let id: i32 = row.try_get("", "id")?;
+                let timestamp: i64 = row.try_get("", "timestamp")?;
+                let data: String = row.try_get("", "data")?;
+
+                cases.push_str(" WHEN ? THEN ?");
+                values.push(id.into());
+                values.push(event_hash_v2(timestamp, &data).into());
+                ids.push(id.into());
+                last_id = id;
+            }
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            values.extend(ids);
+
+            txn.execute(Statement::from_sql_and_values(
+                backend,
+                format!(
+                    "UPDATE logs SET hash = CASE id{} END, hash_version = 2 WHERE id IN ({})",
+                    cases, placeholders
+                ),
+                values,
+            ))
+            .await?;
+        }
+
+        txn.commit().await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // v1 のハッシュは再現できないため、列の削除のみ
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Logs::Table)
+                    .drop_column(Logs::HashVersion)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Logs {
+    Table,
+    HashVersion,
+}
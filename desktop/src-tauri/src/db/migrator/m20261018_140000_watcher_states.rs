@@ -0,0 +1,126 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+use crate::utils::date::str_to_i64;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // WatcherState Table (one row per followed log file)
+        manager
+            .create_table(
+                Table::create()
+                    .table(WatcherStates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WatcherStates::LogPath)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(WatcherStates::IsRunning)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WatcherStates::LastTimestamp)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WatcherStates::LastPosition)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(WatcherStates::Account).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // ログを出力したアカウント (user_id)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Logs::Table)
+                    .add_column(ColumnDef::new(Logs::Account).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // settings に保存されていた単一の WatcherState を移行する
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let rows = db
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT key, value FROM settings WHERE key LIKE 'watcher_%'".to_owned(),
+            ))
+            .await?;
+
+        let mut path = None;
+        let mut is_running = false;
+        let mut timestamp = 0i64;
+        let mut position = 0i64;
+        for row in rows {
+            let key: String = row.try_get("", "key")?;
+            let value: String = row.try_get("", "value")?;
+            match key.as_str() {
+                "watcher_log_path" => path = Some(value).filter(|v| !v.is_empty()),
+                "watcher_is_running" => is_running = value == "1",
+                "watcher_last_timestamp" => timestamp = str_to_i64(&value),
+                "watcher_last_position" => position = value.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+
+        if let Some(p) = path {
+            db.execute(Statement::from_sql_and_values(
+                backend,
+                "INSERT OR IGNORE INTO watcher_states (log_path, is_running, last_timestamp, last_position) VALUES (?, ?, ?, ?)",
+                [p.into(), is_running.into(), timestamp.into(), position.into()],
+            ))
+            .await?;
+        }
+        db.execute(Statement::from_string(
+            backend,
+            "DELETE FROM settings WHERE key LIKE 'watcher_%'".to_owned(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Logs::Table)
+                    .drop_column(Logs::Account)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WatcherStates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum WatcherStates {
+    Table,
+    LogPath,
+    IsRunning,
+    LastTimestamp,
+    LastPosition,
+    Account,
+}
+
+#[derive(Iden)]
+enum Logs {
+    Table,
+    Account,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Imported Files Table (log files already imported by the backfill job)
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImportedFiles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImportedFiles::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ImportedFiles::Path).string().not_null())
+                    .col(ColumnDef::new(ImportedFiles::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(ImportedFiles::ContentHash)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportedFiles::ImportedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_imported_files_identity")
+                    .table(ImportedFiles::Table)
+                    .col(ImportedFiles::Path)
+                    .col(ImportedFiles::Size)
+                    .col(ImportedFiles::ContentHash)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImportedFiles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ImportedFiles {
+    Table,
+    Id,
+    Path,
+    Size,
+    ContentHash,
+    ImportedAt,
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Archived Logs Table (zstd-compressed copies of finished VRChat logs)
+        manager
+            .create_table(
+                Table::create()
+                    .table(ArchivedLogs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ArchivedLogs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ArchivedLogs::FileName).string().not_null())
+                    .col(
+                        ColumnDef::new(ArchivedLogs::ArchivePath)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ArchivedLogs::OriginalSize)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ArchivedLogs::ArchiveSize)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ArchivedLogs::ContentHash)
+                            .big_integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ArchivedLogs::ArchivedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ArchivedLogs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ArchivedLogs {
+    Table,
+    Id,
+    FileName,
+    ArchivePath,
+    OriginalSize,
+    ArchiveSize,
+    ContentHash,
+    ArchivedAt,
+}
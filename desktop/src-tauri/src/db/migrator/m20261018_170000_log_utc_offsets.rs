@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 別の PC で書かれたログとして指定された UTC オフセット (秒。NULL はこの PC のローカル時間)。
+        // 再解析しても同じ時刻になるよう、ファイルごとに保存しておく
+        // (SQLite は1回の ALTER TABLE で1列しか追加できないため、テーブルごとに分ける)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WatcherStates::Table)
+                    .add_column(ColumnDef::new(WatcherStates::UtcOffset).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportedFiles::Table)
+                    .add_column(ColumnDef::new(ImportedFiles::UtcOffset).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ArchivedLogs::Table)
+                    .add_column(ColumnDef::new(ArchivedLogs::UtcOffset).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ArchivedLogs::Table)
+                    .drop_column(ArchivedLogs::UtcOffset)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportedFiles::Table)
+                    .drop_column(ImportedFiles::UtcOffset)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WatcherStates::Table)
+                    .drop_column(WatcherStates::UtcOffset)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum WatcherStates {
+    Table,
+    UtcOffset,
+}
+
+#[derive(Iden)]
+enum ImportedFiles {
+    Table,
+    UtcOffset,
+}
+
+#[derive(Iden)]
+enum ArchivedLogs {
+    Table,
+    UtcOffset,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Process Samples Table (periodic resource usage of running VRChat processes)
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProcessSamples::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProcessSamples::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ProcessSamples::Pid).integer().not_null())
+                    .col(
+                        ColumnDef::new(ProcessSamples::Timestamp)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProcessSamples::CpuUsage).double().not_null())
+                    .col(
+                        ColumnDef::new(ProcessSamples::MemoryBytes)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProcessSamples::ThreadCount).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_process_samples_timestamp")
+                    .table(ProcessSamples::Table)
+                    .col(ProcessSamples::Timestamp)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProcessSamples::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ProcessSamples {
+    Table,
+    Id,
+    Pid,
+    Timestamp,
+    CpuUsage,
+    MemoryBytes,
+    ThreadCount,
+}
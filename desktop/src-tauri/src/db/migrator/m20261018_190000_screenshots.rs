@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Screenshots Table (VRChat screenshots found in logs or in the Pictures folder)
+        manager
+            .create_table(
+                Table::create()
+                    .table(Screenshots::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Screenshots::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Screenshots::FileName)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Screenshots::Path).string().not_null())
+                    .col(
+                        ColumnDef::new(Screenshots::TakenAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Screenshots::Account).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_screenshots_taken_at")
+                    .table(Screenshots::Table)
+                    .col(Screenshots::TakenAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Screenshots::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Screenshots {
+    Table,
+    Id,
+    FileName,
+    Path,
+    TakenAt,
+    Account,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // PNG に埋め込まれた (または書き込んだ) ワールド・撮影者・プレイヤーの情報 (JSON)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Screenshots::Table)
+                    .add_column(ColumnDef::new(Screenshots::Metadata).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Screenshots::Table)
+                    .drop_column(Screenshots::Metadata)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Screenshots {
+    Table,
+    Metadata,
+}
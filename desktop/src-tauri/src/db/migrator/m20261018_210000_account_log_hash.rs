@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement, TransactionTrait};
+use std::collections::HashMap;
+
+use crate::utils::hash::{event_hash_v3, with_sequence};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 1回の UPDATE で書き換える行数
+const BATCH_SIZE: u64 = 500;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+        let txn = db.begin().await?;
+
+        // account の付いた行だけ v3 ハッシュが変わる (account が無ければ v2 と同じ値)。
+        // 同じ秒の同一イベントは watcher と同じく出現順に with_sequence で区別する
+        let mut current_timestamp = None;
+        let mut counts: HashMap<i64, u32> = HashMap::new();
+        loop {
+            // 書き換えた行は hash_version = 3 になり条件から外れるので、毎回先頭から取る
+            let rows = txn
+                .query_all(Statement::from_sql_and_values(
+                    backend,
+                    "SELECT id, timestamp, data, account FROM logs \
+                     WHERE hash_version = 2 AND account IS NOT NULL \
+                     ORDER BY timestamp, id LIMIT ?",
+                    [BATCH_SIZE.into()],
+                ))
+                .await?;
+            if rows.is_empty() {
+                break;
+            }
+
+            let mut cases = String::new();
+            let mut values: Vec<Value> = Vec::with_capacity(rows.len() * 3);
+            let mut ids: Vec<Value> = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let id: i32 = row.try_get("", "id")?;
+                let timestamp: i64 = row.try_get("", "timestamp")?;
+                let data: String = row.try_get("", "data")?;
+                let account: String = row.try_get("", "account")?;
+
+                if current_timestamp != Some(timestamp) {
+                    current_timestamp = Some(timestamp);
+                    counts.clear();
+                }
+                let hash = event_hash_v3(timestamp, &data, Some(&account));
+                let seq = counts.entry(hash).or_insert(0);
+
+                cases.push_str(" WHEN ? THEN ?");
+                values.push(id.into());
+                values.push(with_sequence(hash, *seq).into());
+                ids.push(id.into());
+                *seq += 1;
+            }
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            values.extend(ids);
+
+            txn.execute(Statement::from_sql_and_values(
+                backend,
+                format!(
+                    "UPDATE logs SET hash = CASE id{} END, hash_version = 3 WHERE id IN ({})",
+                    cases, placeholders
+                ),
+                values,
+            ))
+            .await?;
+        }
+
+        txn.execute(Statement::from_string(
+            backend,
+            "UPDATE logs SET hash_version = 3 WHERE hash_version = 2".to_owned(),
+        ))
+        .await?;
+
+        txn.commit().await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // v2 のハッシュ (同じ秒の出現回数) は再現できないため何もしない
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // サンプルを取ったプロセスが書いていたログのアカウント (Login の user_id)。
+        // 複数クライアントを同時に起動していても、セッションごとに自分のプロセスの分だけ集計できる
+        // (NULL はまだログインしていないか、対応するログが分からなかったサンプル)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProcessSamples::Table)
+                    .add_column(ColumnDef::new(ProcessSamples::Account).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProcessSamples::Table)
+                    .drop_column(ProcessSamples::Account)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ProcessSamples {
+    Table,
+    Account,
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 最後の行の秒に出た同一イベントの回数 (EventSequencer の JSON)。
+        // 同じ秒の途中でアプリを再起動しても、続きの番号からハッシュを振るため
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WatcherStates::Table)
+                    .add_column(ColumnDef::new(WatcherStates::Sequencer).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WatcherStates::Table)
+                    .drop_column(WatcherStates::Sequencer)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum WatcherStates {
+    Table,
+    Sequencer,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 各行を実際に変換した UTC オフセット (UtcOffsetRanges の JSON)。
+        // この PC のローカル時間として読んだログも、タイムゾーンが変わった後の再解析で同じ時刻にする
+        // (SQLite は1回の ALTER TABLE で1列しか追加できないため、テーブルごとに分ける)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WatcherStates::Table)
+                    .add_column(ColumnDef::new(WatcherStates::UtcOffsets).text().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportedFiles::Table)
+                    .add_column(ColumnDef::new(ImportedFiles::UtcOffsets).text().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ArchivedLogs::Table)
+                    .add_column(ColumnDef::new(ArchivedLogs::UtcOffsets).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ArchivedLogs::Table)
+                    .drop_column(ArchivedLogs::UtcOffsets)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportedFiles::Table)
+                    .drop_column(ImportedFiles::UtcOffsets)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WatcherStates::Table)
+                    .drop_column(WatcherStates::UtcOffsets)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum WatcherStates {
+    Table,
+    UtcOffsets,
+}
+
+#[derive(Iden)]
+enum ImportedFiles {
+    Table,
+    UtcOffsets,
+}
+
+#[derive(Iden)]
+enum ArchivedLogs {
+    Table,
+    UtcOffsets,
+}
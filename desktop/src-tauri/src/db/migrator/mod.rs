@@ -5,7 +5,35 @@ pub struct Migrator;
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20260420_113556_unnamed_migration::Migration)]
+        vec![
+            Box::new(m20260420_113556_unnamed_migration::Migration),
+            Box::new(m20261018_120000_custom_rules::Migration),
+            Box::new(m20261018_130000_stable_log_hash::Migration),
+            Box::new(m20261018_140000_watcher_states::Migration),
+            Box::new(m20261018_150000_imported_files::Migration),
+            Box::new(m20261018_160000_archived_logs::Migration),
+            Box::new(m20261018_170000_log_utc_offsets::Migration),
+            Box::new(m20261018_180000_process_samples::Migration),
+            Box::new(m20261018_190000_screenshots::Migration),
+            Box::new(m20261018_200000_screenshot_metadata::Migration),
+            Box::new(m20261018_210000_account_log_hash::Migration),
+            Box::new(m20261018_220000_process_sample_account::Migration),
+            Box::new(m20261018_230000_watcher_sequencer::Migration),
+            Box::new(m20261018_240000_log_utc_offset_ranges::Migration),
+        ]
     }
 }
 mod m20260420_113556_unnamed_migration;
+mod m20261018_120000_custom_rules;
+mod m20261018_130000_stable_log_hash;
+mod m20261018_140000_watcher_states;
+mod m20261018_150000_imported_files;
+mod m20261018_160000_archived_logs;
+mod m20261018_170000_log_utc_offsets;
+mod m20261018_180000_process_samples;
+mod m20261018_190000_screenshots;
+mod m20261018_200000_screenshot_metadata;
+mod m20261018_210000_account_log_hash;
+mod m20261018_220000_process_sample_account;
+mod m20261018_230000_watcher_sequencer;
+mod m20261018_240000_log_utc_offset_ranges;
@@ -0,0 +1,75 @@
+use crate::db::schema::archived_logs;
+use sea_orm::*;
+
+pub use archived_logs::Model as ArchivedLog;
+
+pub struct ArchivedLogsRepository {
+    db: DatabaseConnection,
+}
+
+impl ArchivedLogsRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// 古い順
+    pub async fn get_archives(&self) -> Result<Vec<ArchivedLog>, DbErr> {
+        archived_logs::Entity::find()
+            .order_by_asc(archived_logs::Column::ArchivedAt)
+            .order_by_asc(archived_logs::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn is_archived(&self, content_hash: i64) -> Result<bool, DbErr> {
+        let count = archived_logs::Entity::find()
+            .filter(archived_logs::Column::ContentHash.eq(content_hash))
+            .count(&self.db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    pub async fn insert_archive(
+        &self,
+        file_name: &str,
+        archive_path: &str,
+        original_size: u64,
+        archive_size: u64,
+        content_hash: i64,
+        utc_offset: Option<i32>,
+        utc_offsets: Option<String>,
+    ) -> Result<(), DbErr> {
+        let model = archived_logs::ActiveModel {
+            file_name: Set(file_name.to_owned()),
+            archive_path: Set(archive_path.to_owned()),
+            original_size: Set(original_size as i64),
+            archive_size: Set(archive_size as i64),
+            content_hash: Set(content_hash),
+            archived_at: Set(chrono::Utc::now().timestamp_millis()),
+            utc_offset: Set(utc_offset),
+            utc_offsets: Set(utc_offsets),
+            ..Default::default()
+        };
+
+        let res = archived_logs::Entity::insert(model)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::column(archived_logs::Column::ContentHash)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await;
+
+        match res {
+            Ok(_) | Err(DbErr::RecordNotInserted) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn delete_archive(&self, id: i32) -> Result<(), DbErr> {
+        archived_logs::Entity::delete_by_id(id)
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
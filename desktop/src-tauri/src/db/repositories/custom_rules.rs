@@ -0,0 +1,65 @@
+use crate::db::schema::custom_rules;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct CustomRule {
+    pub id: i32,
+    pub name: String,
+    pub pattern: String,
+}
+
+impl From<custom_rules::Model> for CustomRule {
+    fn from(m: custom_rules::Model) -> Self {
+        Self {
+            id: m.id,
+            name: m.name,
+            pattern: m.pattern,
+        }
+    }
+}
+
+pub struct CustomRulesRepository {
+    db: DatabaseConnection,
+}
+
+impl CustomRulesRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_rules(&self) -> Result<Vec<CustomRule>, DbErr> {
+        let rows = custom_rules::Entity::find()
+            .order_by_asc(custom_rules::Column::Id)
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(CustomRule::from).collect())
+    }
+
+    /// 同名のルールがあればパターンを上書きする
+    pub async fn save_rule(&self, name: &str, pattern: &str) -> Result<(), DbErr> {
+        let rule = custom_rules::ActiveModel {
+            name: Set(name.to_owned()),
+            pattern: Set(pattern.to_owned()),
+            ..Default::default()
+        };
+
+        custom_rules::Entity::insert(rule)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::column(custom_rules::Column::Name)
+                    .update_column(custom_rules::Column::Pattern)
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_rule(&self, id: i32) -> Result<(), DbErr> {
+        custom_rules::Entity::delete_by_id(id)
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
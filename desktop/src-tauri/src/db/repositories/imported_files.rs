@@ -0,0 +1,93 @@
+use crate::db::schema::imported_files;
+use sea_orm::*;
+
+pub struct ImportedFilesRepository {
+    db: DatabaseConnection,
+}
+
+impl ImportedFilesRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// 同じ内容のファイルを取り込み済みか
+    pub async fn is_imported(
+        &self,
+        path: &str,
+        size: u64,
+        content_hash: i64,
+    ) -> Result<bool, DbErr> {
+        let count = imported_files::Entity::find()
+            .filter(imported_files::Column::Path.eq(path))
+            .filter(imported_files::Column::Size.eq(size as i64))
+            .filter(imported_files::Column::ContentHash.eq(content_hash))
+            .count(&self.db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// 取り込んだ時に指定された UTC オフセット (再解析で同じ時刻にするため)
+    pub async fn get_utc_offset(&self, path: &str) -> Result<Option<i32>, DbErr> {
+        let row = imported_files::Entity::find()
+            .filter(imported_files::Column::Path.eq(path))
+            .filter(imported_files::Column::UtcOffset.is_not_null())
+            .order_by_desc(imported_files::Column::ImportedAt)
+            .one(&self.db)
+            .await?;
+        Ok(row.and_then(|r| r.utc_offset))
+    }
+
+    /// 取り込んだ時に各行を変換したオフセット (UtcOffsetRanges の JSON)
+    pub async fn get_utc_offsets(&self, path: &str) -> Result<Option<String>, DbErr> {
+        let row = imported_files::Entity::find()
+            .filter(imported_files::Column::Path.eq(path))
+            .filter(imported_files::Column::UtcOffsets.is_not_null())
+            .order_by_desc(imported_files::Column::ImportedAt)
+            .one(&self.db)
+            .await?;
+        Ok(row.and_then(|r| r.utc_offsets))
+    }
+
+    pub async fn mark_imported(
+        &self,
+        path: &str,
+        size: u64,
+        content_hash: i64,
+        utc_offset: Option<i32>,
+        utc_offsets: Option<String>,
+    ) -> Result<(), DbErr> {
+        let model = imported_files::ActiveModel {
+            path: Set(path.to_owned()),
+            size: Set(size as i64),
+            content_hash: Set(content_hash),
+            imported_at: Set(chrono::Utc::now().timestamp_millis()),
+            utc_offset: Set(utc_offset),
+            utc_offsets: Set(utc_offsets),
+            ..Default::default()
+        };
+
+        let res = imported_files::Entity::insert(model)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::columns([
+                    imported_files::Column::Path,
+                    imported_files::Column::Size,
+                    imported_files::Column::ContentHash,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec(&self.db)
+            .await;
+
+        match res {
+            Ok(_) | Err(DbErr::RecordNotInserted) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// ログを全削除した後は、全ファイルを取り込み直せるようにする
+    pub async fn delete_all(&self) -> Result<(), DbErr> {
+        imported_files::Entity::delete_many().exec(&self.db).await?;
+        Ok(())
+    }
+}
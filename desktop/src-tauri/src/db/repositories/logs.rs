@@ -1,5 +1,7 @@
+use crate::db::repositories::watcher_states::{WatcherState, WatcherStatesRepository};
 use crate::db::schema::logs;
 use crate::modules::watcher::{LogPayload, VrcLogEvent};
+use crate::utils::hash::HASH_VERSION;
 use sea_orm::*;
 
 pub struct LogsRepository {
@@ -12,6 +14,37 @@ impl LogsRepository {
     }
 
     pub async fn insert_log(&self, payload: &LogPayload) -> Result<i32, DbErr> {
+        Self::insert_log_with(&self.db, payload).await
+    }
+
+    /// ログと WatcherState を1つのトランザクションで書き込む。
+    /// 途中で落ちても「保存した読み込み位置」と「保存したログ」が食い違わない。挿入できた件数を返す
+    pub async fn insert_batch(
+        &self,
+        payloads: &[LogPayload],
+        states: &[WatcherState],
+    ) -> Result<u32, DbErr> {
+        let txn = self.db.begin().await?;
+        let mut inserted = 0;
+        for payload in payloads {
+            match Self::insert_log_with(&txn, payload).await {
+                Ok(_) => inserted += 1,
+                // 取り込み済み (hash の重複)
+                Err(DbErr::RecordNotInserted) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        for state in states {
+            WatcherStatesRepository::save_state_with(&txn, state).await?;
+        }
+        txn.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn insert_log_with<C: ConnectionTrait>(
+        conn: &C,
+        payload: &LogPayload,
+    ) -> Result<i32, DbErr> {
         let event_type_str = format!("{:?}", payload.event)
             .split_whitespace()
             .next()
@@ -29,6 +62,8 @@ impl LogsRepository {
             event_type: Set(event_type_str),
             data: Set(data_json),
             hash: Set(payload.hash),
+            hash_version: Set(HASH_VERSION),
+            account: Set(payload.account.clone()),
             ..Default::default()
         };
 
@@ -38,7 +73,7 @@ impl LogsRepository {
                     .do_nothing()
                     .to_owned(),
             )
-            .exec(&self.db)
+            .exec(conn)
             .await;
 
         match res {
@@ -105,10 +140,12 @@ impl LogsRepository {
                 .map_err(|e| DbErr::Custom(format!("JSON Parse Error: {}", e)))?;
 
             payloads.push(LogPayload {
+                instance: event.instance_info(),
                 event,
                 // Assign i64 directly from the entity model
                 timestamp: row.timestamp,
                 hash: row.hash,
+                account: row.account,
             });
         }
 
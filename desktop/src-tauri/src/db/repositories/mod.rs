@@ -1,3 +1,9 @@
 // 各テーブルの直接操作用リポジトリをここでまとめて公開する
+pub mod archived_logs;
+pub mod custom_rules;
+pub mod imported_files;
 pub mod logs;
+pub mod process_samples;
+pub mod screenshots;
 pub mod settings;
+pub mod watcher_states;
@@ -0,0 +1,52 @@
+use crate::db::schema::process_samples;
+use sea_orm::*;
+
+pub use process_samples::Model as ProcessSample;
+
+pub struct ProcessSamplesRepository {
+    db: DatabaseConnection,
+}
+
+impl ProcessSamplesRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// 1回の確認で取った全プロセスの分をまとめて書き込む
+    pub async fn insert_samples(&self, samples: &[ProcessSample]) -> Result<(), DbErr> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        let models = samples.iter().map(|s| process_samples::ActiveModel {
+            pid: Set(s.pid),
+            timestamp: Set(s.timestamp),
+            cpu_usage: Set(s.cpu_usage),
+            memory_bytes: Set(s.memory_bytes),
+            thread_count: Set(s.thread_count),
+            account: Set(s.account.clone()),
+            ..Default::default()
+        });
+        process_samples::Entity::insert_many(models)
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// start <= timestamp <= end のサンプルを古い順に
+    pub async fn get_samples(&self, start: i64, end: i64) -> Result<Vec<ProcessSample>, DbErr> {
+        process_samples::Entity::find()
+            .filter(process_samples::Column::Timestamp.between(start, end))
+            .order_by_asc(process_samples::Column::Timestamp)
+            .all(&self.db)
+            .await
+    }
+
+    /// timestamp < before のサンプルを消し、消した件数を返す
+    pub async fn delete_before(&self, before: i64) -> Result<u64, DbErr> {
+        let res = process_samples::Entity::delete_many()
+            .filter(process_samples::Column::Timestamp.lt(before))
+            .exec(&self.db)
+            .await?;
+        Ok(res.rows_affected)
+    }
+}
@@ -0,0 +1,150 @@
+use crate::db::schema::screenshots;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// 写真に写っている場所と人 (PNG のメタデータ)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct ScreenshotMetadata {
+    #[serde(rename = "worldId")]
+    pub world_id: Option<String>,
+    #[serde(rename = "worldName")]
+    pub world_name: Option<String>,
+    #[serde(rename = "instanceId")]
+    pub instance_id: Option<String>,
+    /// 撮影者の表示名
+    pub author: Option<String>,
+    /// 撮影した時点でインスタンスにいたプレイヤーの表示名
+    pub players: Vec<String>,
+    /// VRChat ではなく、このアプリがセッションから復元して書き込んだ
+    #[serde(default)]
+    pub stamped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct Screenshot {
+    pub id: i32,
+    pub path: String,
+    #[serde(rename = "takenAt")]
+    pub taken_at: i64,
+    pub account: Option<String>,
+    pub metadata: Option<ScreenshotMetadata>,
+}
+
+impl From<screenshots::Model> for Screenshot {
+    fn from(m: screenshots::Model) -> Self {
+        Self {
+            id: m.id,
+            path: m.path,
+            taken_at: m.taken_at,
+            account: m.account,
+            metadata: m.metadata.and_then(|json| serde_json::from_str(&json).ok()),
+        }
+    }
+}
+
+/// Windows のパスのまま記録されることもあるため、どちらの区切りでもファイル名を取り出す
+pub fn screenshot_file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+pub struct ScreenshotsRepository {
+    db: DatabaseConnection,
+}
+
+impl ScreenshotsRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// この PC で開けるパスのファイルを記録する (フォルダのスキャン、またはログの Screenshot イベントから)。
+    /// 記録済みならパスを置き換え、ログから分かった撮影アカウントが無ければ補う。
+    /// 新しく追加した場合は true
+    pub async fn upsert_local(
+        &self,
+        path: &str,
+        taken_at: i64,
+        account: Option<&str>,
+    ) -> Result<bool, DbErr> {
+        let file_name = screenshot_file_name(path);
+        let existing = screenshots::Entity::find()
+            .filter(screenshots::Column::FileName.eq(file_name))
+            .one(&self.db)
+            .await?;
+
+        match existing {
+            Some(row) if row.path == path && (row.account.is_some() || account.is_none()) => {
+                Ok(false)
+            }
+            Some(row) => {
+                let fill_account = row.account.is_none() && account.is_some();
+                let mut model: screenshots::ActiveModel = row.into();
+                model.path = Set(path.to_owned());
+                if fill_account {
+                    model.account = Set(account.map(str::to_owned));
+                }
+                model.update(&self.db).await?;
+                Ok(false)
+            }
+            None => {
+                let model = screenshots::ActiveModel {
+                    file_name: Set(file_name.to_owned()),
+                    path: Set(path.to_owned()),
+                    taken_at: Set(taken_at),
+                    account: Set(account.map(str::to_owned)),
+                    metadata: Set(None),
+                    ..Default::default()
+                };
+                model.insert(&self.db).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// start <= taken_at <= end のスクリーンショットを古い順に
+    pub async fn get_screenshots(&self, start: i64, end: i64) -> Result<Vec<Screenshot>, DbErr> {
+        let rows = screenshots::Entity::find()
+            .filter(screenshots::Column::TakenAt.between(start, end))
+            .order_by_asc(screenshots::Column::TakenAt)
+            .order_by_asc(screenshots::Column::Id)
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Screenshot::from).collect())
+    }
+
+    /// メタデータをまだ読めていないもの
+    pub async fn get_without_metadata(&self) -> Result<Vec<Screenshot>, DbErr> {
+        let rows = screenshots::Entity::find()
+            .filter(screenshots::Column::Metadata.is_null())
+            .order_by_asc(screenshots::Column::TakenAt)
+            .all(&self.db)
+            .await?;
+        Ok(rows.into_iter().map(Screenshot::from).collect())
+    }
+
+    pub async fn set_metadata(&self, id: i32, metadata: &ScreenshotMetadata) -> Result<(), DbErr> {
+        let json = serde_json::to_string(metadata).map_err(|e| DbErr::Custom(e.to_string()))?;
+        screenshots::Entity::update_many()
+            .col_expr(
+                screenshots::Column::Metadata,
+                sea_orm::sea_query::Expr::value(json),
+            )
+            .filter(screenshots::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_by_file_name(&self, file_name: &str) -> Result<Option<Screenshot>, DbErr> {
+        let row = screenshots::Entity::find()
+            .filter(screenshots::Column::FileName.eq(file_name))
+            .one(&self.db)
+            .await?;
+        Ok(row.map(Screenshot::from))
+    }
+
+    pub async fn get_screenshot(&self, id: i32) -> Result<Option<Screenshot>, DbErr> {
+        let row = screenshots::Entity::find_by_id(id).one(&self.db).await?;
+        Ok(row.map(Screenshot::from))
+    }
+}
@@ -1,13 +1,23 @@
 use crate::db::schema::settings;
 use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use specta::Type;
 
-// 元のコードにあった WatcherState 定義
-#[derive(Debug, Clone)]
-pub struct WatcherState {
-    pub log_path: String,
-    pub is_running: bool,
-    pub last_timestamp: String,
-    pub last_position: u64,
+/// ログアーカイブの保持上限 (0 は無制限)
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ArchiveRetention {
+    pub days: u32,
+    #[serde(rename = "maxBytes")]
+    pub max_bytes: u64,
+}
+
+impl Default for ArchiveRetention {
+    fn default() -> Self {
+        Self {
+            days: 90,
+            max_bytes: 1024 * 1024 * 1024, // 1 GiB
+        }
+    }
 }
 
 pub struct SettingsRepository {
@@ -43,81 +53,52 @@ impl SettingsRepository {
         Ok(())
     }
 
-    /// Save Watcher State
-    pub async fn save_watcher_state(&self, state: &WatcherState) -> Result<(), DbErr> {
-        // トランザクションで一括保存
-        let txn = self.db.begin().await?;
-
-        let items = vec![
-            ("watcher_log_path", state.log_path.clone()),
-            (
-                "watcher_is_running",
-                if state.is_running {
-                    "1".to_string()
-                } else {
-                    "0".to_string()
-                },
-            ),
-            ("watcher_last_timestamp", state.last_timestamp.clone()),
-            ("watcher_last_position", state.last_position.to_string()),
-        ];
+    /// Generic deleter
+    pub async fn delete_setting(&self, key: &str) -> Result<(), DbErr> {
+        settings::Entity::delete_by_id(key).exec(&self.db).await?;
+        Ok(())
+    }
 
-        for (k, v) in items {
-            let active_model = settings::ActiveModel {
-                key: Set(k.to_string()),
-                value: Set(v),
-            };
+    /// VRChat log directory override (None = auto-detect)
+    pub async fn get_log_dir(&self) -> Result<Option<String>, DbErr> {
+        let dir = self.get_setting("log_dir").await?;
+        Ok(dir.filter(|d| !d.is_empty()))
+    }
 
-            settings::Entity::insert(active_model)
-                .on_conflict(
-                    sea_orm::sea_query::OnConflict::column(settings::Column::Key)
-                        .update_column(settings::Column::Value)
-                        .to_owned(),
-                )
-                .exec(&txn)
-                .await?;
+    pub async fn set_log_dir(&self, dir: Option<&str>) -> Result<(), DbErr> {
+        match dir {
+            Some(d) if !d.is_empty() => self.set_setting("log_dir", d).await,
+            _ => self.delete_setting("log_dir").await,
         }
+    }
 
-        txn.commit().await?;
-        Ok(())
+    pub async fn get_archive_retention(&self) -> Result<ArchiveRetention, DbErr> {
+        let default = ArchiveRetention::default();
+        let days = self.get_setting("archive_retention_days").await?;
+        let max_bytes = self.get_setting("archive_max_bytes").await?;
+        Ok(ArchiveRetention {
+            days: days.and_then(|v| v.parse().ok()).unwrap_or(default.days),
+            max_bytes: max_bytes
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_bytes),
+        })
     }
 
-    /// Get Watcher State
-    pub async fn get_watcher_state(&self) -> Result<Option<WatcherState>, DbErr> {
-        // 'watcher_%' に一致するキーを取得
-        let rows = settings::Entity::find()
-            .filter(settings::Column::Key.starts_with("watcher_"))
-            .all(&self.db)
+    pub async fn set_archive_retention(&self, retention: &ArchiveRetention) -> Result<(), DbErr> {
+        self.set_setting("archive_retention_days", &retention.days.to_string())
             .await?;
+        self.set_setting("archive_max_bytes", &retention.max_bytes.to_string())
+            .await
+    }
 
-        let mut path = None;
-        let mut is_running = false;
-        let mut timestamp = None;
-        let mut position = 0;
-
-        for row in rows {
-            match row.key.as_str() {
-                "watcher_log_path" => path = Some(row.value),
-                "watcher_is_running" => is_running = row.value == "1",
-                "watcher_last_timestamp" => timestamp = Some(row.value),
-                "watcher_last_position" => {
-                    if let Ok(p) = row.value.parse::<u64>() {
-                        position = p;
-                    }
-                }
-                _ => {}
-            }
-        }
+    /// メタデータの無いスクリーンショットに、セッションから復元した情報を書き込むか (既定は無効)
+    pub async fn get_screenshot_stamping(&self) -> Result<bool, DbErr> {
+        let value = self.get_setting("screenshot_stamping").await?;
+        Ok(value.as_deref() == Some("1"))
+    }
 
-        if let (Some(p), Some(ts)) = (path, timestamp) {
-            Ok(Some(WatcherState {
-                log_path: p,
-                is_running,
-                last_timestamp: ts,
-                last_position: position,
-            }))
-        } else {
-            Ok(None)
-        }
+    pub async fn set_screenshot_stamping(&self, enabled: bool) -> Result<(), DbErr> {
+        self.set_setting("screenshot_stamping", if enabled { "1" } else { "0" })
+            .await
     }
 }
@@ -0,0 +1,103 @@
+use crate::db::schema::watcher_states;
+use sea_orm::*;
+
+/// ログファイルごとの追跡状態
+#[derive(Debug, Clone)]
+pub struct WatcherState {
+    pub log_path: String,
+    pub is_running: bool,
+    pub last_timestamp: i64,
+    pub last_position: u64,
+    pub account: Option<String>,
+    /// 別の PC のログとして指定された UTC オフセット (秒)。None はこの PC のローカル時間
+    pub utc_offset: Option<i32>,
+    /// 各行を実際に変換した UTC オフセット (UtcOffsetRanges の JSON)
+    pub utc_offsets: Option<String>,
+    /// 最後の行の秒に出た同一イベントの回数 (EventSequencer の JSON)
+    pub sequencer: Option<String>,
+}
+
+impl From<watcher_states::Model> for WatcherState {
+    fn from(m: watcher_states::Model) -> Self {
+        Self {
+            log_path: m.log_path,
+            is_running: m.is_running,
+            last_timestamp: m.last_timestamp,
+            last_position: m.last_position.max(0) as u64,
+            account: m.account,
+            utc_offset: m.utc_offset,
+            utc_offsets: m.utc_offsets,
+            sequencer: m.sequencer,
+        }
+    }
+}
+
+pub struct WatcherStatesRepository {
+    db: DatabaseConnection,
+}
+
+impl WatcherStatesRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_states(&self) -> Result<Vec<WatcherState>, DbErr> {
+        let rows = watcher_states::Entity::find().all(&self.db).await?;
+        Ok(rows.into_iter().map(WatcherState::from).collect())
+    }
+
+    pub async fn get_state(&self, log_path: &str) -> Result<Option<WatcherState>, DbErr> {
+        let row = watcher_states::Entity::find_by_id(log_path.to_owned())
+            .one(&self.db)
+            .await?;
+        Ok(row.map(WatcherState::from))
+    }
+
+    /// Save Watcher State (Upsert by log_path)
+    pub async fn save_state(&self, state: &WatcherState) -> Result<(), DbErr> {
+        Self::save_state_with(&self.db, state).await
+    }
+
+    /// トランザクション内でも使えるよう、接続を受け取る版
+    pub(crate) async fn save_state_with<C: ConnectionTrait>(
+        conn: &C,
+        state: &WatcherState,
+    ) -> Result<(), DbErr> {
+        let model = watcher_states::ActiveModel {
+            log_path: Set(state.log_path.clone()),
+            is_running: Set(state.is_running),
+            last_timestamp: Set(state.last_timestamp),
+            last_position: Set(state.last_position as i64),
+            account: Set(state.account.clone()),
+            utc_offset: Set(state.utc_offset),
+            utc_offsets: Set(state.utc_offsets.clone()),
+            sequencer: Set(state.sequencer.clone()),
+        };
+
+        watcher_states::Entity::insert(model)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::column(watcher_states::Column::LogPath)
+                    .update_columns([
+                        watcher_states::Column::IsRunning,
+                        watcher_states::Column::LastTimestamp,
+                        watcher_states::Column::LastPosition,
+                        watcher_states::Column::Account,
+                        watcher_states::Column::UtcOffset,
+                        watcher_states::Column::UtcOffsets,
+                        watcher_states::Column::Sequencer,
+                    ])
+                    .to_owned(),
+            )
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// 追跡を終えたファイルの状態を削除する
+    pub async fn delete_state(&self, log_path: &str) -> Result<(), DbErr> {
+        watcher_states::Entity::delete_by_id(log_path.to_owned())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "imported_files")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub path: String,
+    pub size: i64,
+    pub content_hash: i64,           // xxh3_64 of the whole file
+    pub imported_at: i64,            // milliseconds since epoch
+    pub utc_offset: Option<i32>, // seconds east of UTC given for a log written on another PC (None: this PC's local time)
+    pub utc_offsets: Option<String>, // JSON of the offsets each line range was actually converted with
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -11,6 +11,8 @@ pub struct Model {
     pub data: String, // JSON stored as string
     #[sea_orm(unique)]
     pub hash: i64,
+    pub hash_version: i32,
+    pub account: Option<String>, // user_id of the client that wrote the log
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
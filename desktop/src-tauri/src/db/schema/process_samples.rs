@@ -0,0 +1,20 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "process_samples")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub pid: i32,
+    pub timestamp: i64,            // milliseconds since epoch
+    pub cpu_usage: f64,            // percent of one core (can exceed 100)
+    pub memory_bytes: i64,         // resident set size
+    pub thread_count: Option<i32>, // not available on every platform
+    pub account: Option<String>,   // user_id of the log the process was writing
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
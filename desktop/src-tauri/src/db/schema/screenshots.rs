@@ -0,0 +1,20 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "screenshots")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub file_name: String, // VRChat_YYYY-MM-DD_HH-MM-SS.mmm_WxH.png
+    pub path: String,  // as logged, or the local path once found by a folder scan
+    pub taken_at: i64, // milliseconds since epoch
+    pub account: Option<String>, // user_id of the client that took it (log only)
+    pub metadata: Option<String>, // JSON of ScreenshotMetadata read from (or written to) the PNG
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "watcher_states")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub log_path: String,
+    pub is_running: bool,
+    pub last_timestamp: i64, // milliseconds since epoch
+    pub last_position: i64,  // byte offset of the last confirmed line boundary
+    pub account: Option<String>,
+    pub utc_offset: Option<i32>, // seconds east of UTC given for a log written on another PC (None: this PC's local time)
+    pub utc_offsets: Option<String>, // JSON of the offsets each line range was actually converted with
+    pub sequencer: Option<String>,   // JSON of the duplicate counts within the last second
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
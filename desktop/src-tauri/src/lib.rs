@@ -4,12 +4,15 @@ pub mod modules;
 pub mod utils;
 use tauri::Manager;
 use tauri_specta::{collect_commands, collect_events, Builder as SpectaBuilder};
+use tokio_util::sync::CancellationToken;
 
 pub struct Ctx {
     db: db::DB,
     srv: modules::HttpSrv,
     watcher: modules::WatcherService,
     vrcapi: modules::VrcApiService,
+    /// 終了時に各タスクへ通知する
+    shutdown: CancellationToken,
 }
 
 // ---------------------------------------------------------
@@ -25,14 +28,38 @@ pub fn create_specta_builder() -> SpectaBuilder {
             cmds::vrclog::logs::export_logs,
             cmds::vrclog::logs::get_logs,
             cmds::vrclog::logs::delete_all_logs,
+            cmds::vrclog::logs::start_backfill,
+            cmds::vrclog::logs::get_parser_version,
+            cmds::vrclog::logs::reparse_logs,
             cmds::vrclog::sessions::get_sessions,
+            cmds::vrclog::screenshots::get_session_screenshots,
+            cmds::vrclog::screenshots::scan_screenshots,
+            cmds::vrclog::screenshots::get_screenshot_stamping,
+            cmds::vrclog::screenshots::set_screenshot_stamping,
+            cmds::vrclog::rules::get_custom_rules,
+            cmds::vrclog::rules::add_custom_rule,
+            cmds::vrclog::rules::test_custom_rule,
+            cmds::vrclog::rules::remove_custom_rule,
+            cmds::vrclog::settings::get_log_dir,
+            cmds::vrclog::settings::set_log_dir,
+            cmds::vrclog::settings::list_log_dir_candidates,
+            cmds::vrclog::settings::get_archive_retention,
+            cmds::vrclog::settings::set_archive_retention,
+            cmds::vrclog::watcher::get_watcher_status,
+            cmds::vrclog::watcher::pause_watcher,
+            cmds::vrclog::watcher::resume_watcher,
+            cmds::vrclog::watcher::rescan_watcher,
+            cmds::vrclog::watcher::switch_watcher_file,
             cmds::vrcapi::auth::login,
             cmds::vrcapi::auth::logout,
             cmds::vrcapi::auth::verify_2fa
         ])
         .events(collect_events![
             modules::watcher::LogPayload,
-            modules::watcher::VrcLogEvent
+            modules::watcher::VrcLogEvent,
+            modules::backfill::BackfillProgress,
+            modules::watcher::WatcherStatusChanged,
+            modules::process::VrcProcessChanged
         ])
 }
 
@@ -74,10 +101,21 @@ pub fn run() {
                 modules::VrcApiService::new(app_data_dir).expect("Failed to init VrcApiService");
             app.manage(vrcapi.clone());
 
+            // 終了シグナル (watcher, http srv などで共有)
+            let shutdown = CancellationToken::new();
+
             // ログ監視開始
-            let watcher = modules::watcher::spawn_log_watcher(app.handle().clone(), db.clone());
+            let watcher = modules::watcher::spawn_log_watcher(
+                app.handle().clone(),
+                db.clone(),
+                shutdown.clone(),
+            );
+            // 初回起動時はログディレクトリに残っている過去ログを取り込む
+            modules::backfill::spawn_initial_backfill(app.handle().clone(), db.clone());
+            // ログに残っていないスクリーンショットも拾う
+            modules::screenshots::spawn_screenshot_scan(db.clone());
             // http srv 起動
-            let srv = modules::http::HttpSrv::new(db.clone());
+            let srv = modules::http::HttpSrv::new(db.clone(), shutdown.clone());
             // 常駐化設定
             modules::systray::setup_tray(app.handle())?;
 
@@ -99,11 +137,21 @@ pub fn run() {
                 srv,
                 watcher,
                 vrcapi,
+                shutdown,
             };
             app.manage(ctx); // グローバルステートとしてCtxを登録
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // 終了処理 (書き込みの完了待ち) が終わるまでは終了させない
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if !modules::shutdown::is_finished() {
+                    api.prevent_exit();
+                    modules::shutdown::exit(app, false);
+                }
+            }
+        });
 }
@@ -0,0 +1,251 @@
+use crate::db::repositories::archived_logs::ArchivedLog;
+use crate::db::DB;
+use crate::modules::backfill::file_fingerprint_blocking;
+use crate::modules::watcher::{log_file_name_time, UtcOffsetRanges};
+use crate::utils::date::{naive_to_i64, resolve_local_offset};
+use chrono::FixedOffset;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// zstd の圧縮レベル。ログは数百 MB になることがあり、19 などの高いレベルでは
+/// 1ファイルに数分かかるため、十分に縮む既定の 3 にする (圧縮は blocking スレッドで行う)
+const COMPRESSION_LEVEL: i32 = 3;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// アーカイブの保存先 (app_local_data_dir/log_archive)
+pub fn archive_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_local_data_dir()
+        .ok()
+        .map(|dir| dir.join("log_archive"))
+}
+
+/// 一時ファイルに書き出してから rename するので、途中で落ちても壊れたアーカイブは残らない
+fn compress_file(src: &Path, dest: &Path) -> io::Result<u64> {
+    let part = dest.with_extension("zst.part");
+    let result = (|| {
+        let reader = BufReader::new(File::open(src)?);
+        let mut writer = BufWriter::new(File::create(&part)?);
+        zstd::stream::copy_encode(reader, &mut writer, COMPRESSION_LEVEL)?;
+        writer.flush()?;
+        fs::rename(&part, dest)?;
+        Ok(fs::metadata(dest)?.len())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&part);
+    }
+    result
+}
+
+/// アーカイブを展開して元のログファイルに戻す
+pub fn decompress_file(archive: &Path, dest: &Path) -> io::Result<()> {
+    let reader = BufReader::new(File::open(archive)?);
+    let mut writer = BufWriter::new(File::create(dest)?);
+    zstd::stream::copy_decode(reader, &mut writer)?;
+    writer.flush()
+}
+
+/// 書き込みの終わったログファイルを圧縮してアーカイブに追加する。
+/// utc_offset は別の PC のログとして指定されたオフセット、utc_offsets は各行を実際に変換したオフセットで、
+/// 再解析で同じ時刻にするために一緒に保存する。
+/// 同じ内容のファイルが既にあれば何もしない (新しく追加した場合は true)
+pub async fn archive_log_file(
+    db: &DB,
+    archive_dir: &Path,
+    path: &Path,
+    utc_offset: Option<i32>,
+    utc_offsets: &UtcOffsetRanges,
+) -> Result<bool, String> {
+    let (size, content_hash) = file_fingerprint_blocking(path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if db
+        .archived_logs()
+        .is_archived(content_hash)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        return Ok(false);
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Invalid log file path")?;
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dest = archive_dir.join(format!("{}_{:016x}.txt.zst", stem, content_hash as u64));
+
+    let src = path.to_path_buf();
+    let dir = archive_dir.to_path_buf();
+    let dest_clone = dest.clone();
+    let archive_size = tauri::async_runtime::spawn_blocking(move || {
+        fs::create_dir_all(&dir)?;
+        compress_file(&src, &dest_clone)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    db.archived_logs()
+        .insert_archive(
+            &file_name,
+            &dest.to_string_lossy(),
+            size,
+            archive_size,
+            content_hash,
+            utc_offset,
+            utc_offsets.to_json(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Archived log file: {:?} ({} -> {} bytes)",
+        path, size, archive_size
+    );
+    Ok(true)
+}
+
+/// ログ自体の時刻 (ファイル名の作成時刻)。保持期間はアーカイブした日ではなくここから数える
+/// (古いログを後からバックフィルしてアーカイブしても、保持期間が延びない)。
+/// ファイル名から分からなければアーカイブした時刻にする
+fn logged_at(archive: &ArchivedLog) -> i64 {
+    log_file_name_time(Path::new(&archive.file_name))
+        .map(|ndt| {
+            let offset = archive
+                .utc_offset
+                .and_then(FixedOffset::east_opt)
+                .unwrap_or_else(|| resolve_local_offset(&ndt));
+            naive_to_i64(&ndt, offset)
+        })
+        .unwrap_or(archive.archived_at)
+}
+
+/// 保持期間・合計サイズの上限を超えた古いアーカイブを削除する。削除した数を返す
+pub async fn apply_retention(db: &DB) -> Result<u32, String> {
+    let retention = db
+        .settings()
+        .get_archive_retention()
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut archives = db
+        .archived_logs()
+        .get_archives()
+        .await
+        .map_err(|e| e.to_string())?;
+    archives.sort_by_key(logged_at);
+
+    let cutoff = chrono::Utc::now().timestamp_millis() - retention.days as i64 * DAY_MS;
+    let mut total_bytes: u64 = archives.iter().map(|a| a.archive_size as u64).sum();
+    let mut removed = 0;
+
+    // ログの古い順に並べたので、先頭から上限に収まるまで消す
+    for archive in archives {
+        let expired = retention.days > 0 && logged_at(&archive) < cutoff;
+        let over_budget = retention.max_bytes > 0 && total_bytes > retention.max_bytes;
+        if !expired && !over_budget {
+            break;
+        }
+
+        match fs::remove_file(&archive.archive_path) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("Failed to remove archive {}: {}", archive.archive_path, e);
+                continue;
+            }
+        }
+        db.archived_logs()
+            .delete_archive(archive.id)
+            .await
+            .map_err(|e| e.to_string())?;
+        total_bytes = total_bytes.saturating_sub(archive.archive_size as u64);
+        removed += 1;
+    }
+
+    Ok(removed)
+}
+
+/// watcher から呼ばれる (追跡を終えたファイルをバックグラウンドでアーカイブ)
+pub fn spawn_archive(
+    app: AppHandle,
+    db: DB,
+    path: PathBuf,
+    utc_offset: Option<i32>,
+    utc_offsets: UtcOffsetRanges,
+) {
+    tauri::async_runtime::spawn(async move {
+        let Some(dir) = archive_dir(&app) else {
+            return;
+        };
+        if let Err(e) = archive_log_file(&db, &dir, &path, utc_offset, &utc_offsets).await {
+            eprintln!("Failed to archive {:?}: {}", path, e);
+            return;
+        }
+        if let Err(e) = apply_retention(&db).await {
+            eprintln!("Failed to apply archive retention: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::repositories::settings::ArchiveRetention;
+
+    async fn archive(db: &DB, dir: &Path, file_name: &str, content_hash: i64) -> PathBuf {
+        let path = dir.join(format!("{}.zst", file_name));
+        fs::write(&path, b"archive").unwrap();
+        db.archived_logs()
+            .insert_archive(
+                file_name,
+                &path.to_string_lossy(),
+                100,
+                7,
+                content_hash,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn measures_retention_from_the_time_of_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::new(dir.path().join("data")).await.unwrap();
+        db.settings()
+            .set_archive_retention(&ArchiveRetention {
+                days: 30,
+                max_bytes: 0,
+            })
+            .await
+            .unwrap();
+
+        // どちらも今アーカイブしたが、1つは1年前のログをバックフィルしたもの
+        let now = chrono::Local::now().naive_local();
+        let name = |days: i64| {
+            format!(
+                "output_log_{}.txt",
+                (now - chrono::Duration::days(days)).format("%Y-%m-%d_%H-%M-%S")
+            )
+        };
+        let old = archive(&db, dir.path(), &name(365), 1).await;
+        let recent = archive(&db, dir.path(), &name(1), 2).await;
+
+        assert_eq!(apply_retention(&db).await.unwrap(), 1);
+        assert!(!old.exists());
+        assert!(recent.exists());
+        let archives = db.archived_logs().get_archives().await.unwrap();
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].content_hash, 2);
+    }
+}
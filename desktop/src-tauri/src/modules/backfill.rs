@@ -0,0 +1,456 @@
+use crate::db::DB;
+use crate::modules::archiver;
+use crate::modules::screenshots;
+use crate::modules::watcher::{
+    get_vrc_log_dir, idle_duration, list_log_files, sort_log_files, LogPayload, LogTail, TailRead,
+    UtcOffsetRanges, VrcLogEvent, ACTIVE_WINDOW, PARSER_VERSION,
+};
+use chrono::FixedOffset;
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+use tauri_specta::Event;
+use xxhash_rust::xxh3::Xxh3;
+
+const BACKFILL_DONE_KEY: &str = "backfill_done";
+const PARSER_VERSION_KEY: &str = "parser_version";
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 1ファイル分の取り込み結果
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+pub struct ImportResult {
+    pub inserted: u32,
+    pub skipped: u32,
+    #[serde(rename = "malformedLines")]
+    pub malformed_lines: u64,
+    /// セッションの途中で終わっていた (InvalidAppStop を挿入した)
+    #[serde(rename = "endedMidSession")]
+    pub ended_mid_session: bool,
+    /// 指定された UTC オフセット (秒)。None はこの PC のローカル時間
+    #[serde(rename = "utcOffset")]
+    pub utc_offset: Option<i32>,
+    /// 各行を実際に変換したオフセット (UtcOffsetRanges の JSON)。取り込み記録とアーカイブに保存する
+    #[serde(skip)]
+    pub utc_offsets: Option<String>,
+}
+
+/// 再解析の結果
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+pub struct ReparseResult {
+    pub files: u32,
+    pub inserted: u32,
+    pub skipped: u32,
+}
+
+/// 現在のパーサーと、保存済みのログを解析したパーサーのバージョン
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct ParserVersion {
+    pub current: u32,
+    pub stored: u32,
+}
+
+/// バックフィルの進捗 (ファイルを1つ処理するごとに送る)
+#[derive(Clone, Serialize, Deserialize, Type, Event)]
+pub struct BackfillProgress {
+    pub total: u32,
+    pub processed: u32,
+    #[serde(rename = "currentFile")]
+    pub current_file: Option<String>,
+    pub inserted: u32,
+    pub done: bool,
+}
+
+/// 取り込むログをまとめて書き込む件数
+const BATCH_SIZE: usize = 500;
+
+/// 1トランザクションで書き込み、バッファを空にする
+async fn insert_batch(db: &DB, batch: &mut Vec<LogPayload>, result: &mut ImportResult) {
+    if batch.is_empty() {
+        return;
+    }
+    match db.logs().insert_batch(batch, &[]).await {
+        Ok(inserted) => {
+            result.inserted += inserted;
+            // 取り込み済み (hash の重複)
+            result.skipped += batch.len() as u32 - inserted;
+        }
+        Err(e) => {
+            eprintln!("\tinsert error: {}", e);
+            result.skipped += batch.len() as u32;
+        }
+    }
+    batch.clear();
+}
+
+/// ファイル全体の (サイズ, 内容ハッシュ)
+pub fn file_fingerprint(path: &Path) -> io::Result<(u64, i64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, hasher.digest() as i64))
+}
+
+/// file_fingerprint をランタイムを塞がないよう blocking スレッドで計算する
+pub async fn file_fingerprint_blocking(path: &Path) -> io::Result<(u64, i64)> {
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || file_fingerprint(&path))
+        .await
+        .map_err(|e| io::Error::other(e.to_string()))?
+}
+
+/// 書き込みの終わったログファイルを先頭から取り込む。
+/// watcher と同じ処理 (LogTail) を使うので、同じファイルを何度取り込んでも結果は変わらない。
+/// 読み込みと解析は blocking スレッドで行い、解析済みのログを channel 経由で受け取って書き込む。
+/// utc_offset を省略した場合は、この PC のローカル時間として1行ずつ変換する。
+/// utc_offsets は以前に読んだ時に記録したオフセットで、記録済みの行はそれで変換する
+pub async fn import_log_file(
+    db: &DB,
+    path: &Path,
+    utc_offset: Option<FixedOffset>,
+    utc_offsets: UtcOffsetRanges,
+) -> io::Result<ImportResult> {
+    File::open(path)?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<LogPayload>>(4);
+    let path = path.to_path_buf();
+    let reader = tauri::async_runtime::spawn_blocking(move || {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut ended_mid_session = false;
+        let mut tail = LogTail::open(path, None)
+            .with_utc_offset(utc_offset)
+            .with_utc_offsets(utc_offsets);
+        loop {
+            match tail.read() {
+                TailRead::Payload(payload) => {
+                    batch.push(payload);
+                    if batch.len() >= BATCH_SIZE
+                        && tx.blocking_send(std::mem::take(&mut batch)).is_err()
+                    {
+                        // 受け取り側が終了した
+                        break;
+                    }
+                }
+                TailRead::Skipped => {}
+                TailRead::Eof => break,
+            }
+        }
+
+        for payload in tail.finish() {
+            if matches!(payload.event, VrcLogEvent::InvalidAppStop) {
+                ended_mid_session = true;
+            }
+            batch.push(payload);
+        }
+        let _ = tx.blocking_send(batch);
+        (
+            tail.malformed_lines,
+            ended_mid_session,
+            tail.utc_offset_secs(),
+            tail.utc_offsets().to_json(),
+        )
+    });
+
+    let mut result = ImportResult::default();
+    while let Some(mut batch) = rx.recv().await {
+        screenshots::record_logged_payloads(db, &batch).await;
+        insert_batch(db, &mut batch, &mut result).await;
+    }
+
+    let (malformed_lines, ended_mid_session, used_offset, used_offsets) =
+        reader.await.map_err(|e| io::Error::other(e.to_string()))?;
+    result.malformed_lines = malformed_lines;
+    result.ended_mid_session = ended_mid_session;
+    result.utc_offset = used_offset;
+    result.utc_offsets = used_offsets;
+
+    Ok(result)
+}
+
+/// 以前にこのファイルを読んだ時に各行を変換したオフセット (取り込み記録 > watcher の状態の順)。
+/// 読み直しても同じ時刻になり、取り込み済みのイベントと重複しない
+async fn saved_utc_offsets(db: &DB, path_str: &str) -> UtcOffsetRanges {
+    let json = match db.imported_files().get_utc_offsets(path_str).await {
+        Ok(Some(json)) => Some(json),
+        _ => db
+            .watcher_states()
+            .get_state(path_str)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.utc_offsets),
+    };
+    UtcOffsetRanges::from_json(json.as_deref())
+}
+
+/// アクティブでない (watcher が追跡していない) ログファイルを古い順に列挙する
+async fn finished_log_files(db: &DB) -> Option<Vec<PathBuf>> {
+    let log_dir = get_vrc_log_dir(db).await?;
+
+    // 書き込み中のファイルは watcher が追跡しているので対象外
+    let mut files = list_log_files(&log_dir);
+    sort_log_files(&mut files);
+    files.pop();
+    files.retain(|path| idle_duration(path) >= ACTIVE_WINDOW);
+    Some(files)
+}
+
+/// ログディレクトリ内の未取り込みのファイルを全て取り込む。取り込んだファイル数を返す
+pub async fn run_backfill(app: &AppHandle, db: &DB) -> Result<u32, String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Backfill is already running".to_string());
+    }
+    let result = backfill(app, db).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+pub fn is_backfill_running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+async fn backfill(app: &AppHandle, db: &DB) -> Result<u32, String> {
+    let files = finished_log_files(db)
+        .await
+        .ok_or("VRChat log directory not found")?;
+
+    let mut progress = BackfillProgress {
+        total: files.len() as u32,
+        processed: 0,
+        current_file: None,
+        inserted: 0,
+        done: false,
+    };
+    let mut imported_files = 0;
+    let archive_dir = archiver::archive_dir(app);
+
+    for path in files {
+        let path_str = path.to_string_lossy().to_string();
+        progress.current_file = Some(path_str.clone());
+        let mut utc_offset = None;
+        let mut utc_offsets = None;
+
+        match file_fingerprint_blocking(&path).await {
+            Ok((size, content_hash)) => {
+                let seen = db
+                    .imported_files()
+                    .is_imported(&path_str, size, content_hash)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                if !seen {
+                    println!("Backfilling: {:?}", path);
+                    let saved = saved_utc_offsets(db, &path_str).await;
+                    match import_log_file(db, &path, None, saved).await {
+                        Ok(result) => {
+                            progress.inserted += result.inserted;
+                            imported_files += 1;
+                            utc_offset = result.utc_offset;
+                            utc_offsets =
+                                Some(UtcOffsetRanges::from_json(result.utc_offsets.as_deref()));
+                            db.imported_files()
+                                .mark_imported(
+                                    &path_str,
+                                    size,
+                                    content_hash,
+                                    result.utc_offset,
+                                    result.utc_offsets,
+                                )
+                                .await
+                                .map_err(|e| e.to_string())?;
+                        }
+                        Err(e) => eprintln!("  -> Error processing file: {}", e),
+                    }
+                }
+            }
+            Err(e) => eprintln!("Failed to read {:?}: {}", path, e),
+        }
+
+        // 既に取り込み済みのファイルもアーカイブには残す (同じ内容なら何もしない)
+        if let Some(dir) = &archive_dir {
+            if utc_offset.is_none() {
+                utc_offset = db
+                    .imported_files()
+                    .get_utc_offset(&path_str)
+                    .await
+                    .ok()
+                    .flatten();
+            }
+            let utc_offsets = match utc_offsets {
+                Some(utc_offsets) => utc_offsets,
+                None => saved_utc_offsets(db, &path_str).await,
+            };
+            if let Err(e) =
+                archiver::archive_log_file(db, dir, &path, utc_offset, &utc_offsets).await
+            {
+                eprintln!("Failed to archive {:?}: {}", path, e);
+            }
+        }
+
+        progress.processed += 1;
+        let _ = progress.emit(app);
+    }
+
+    if let Err(e) = archiver::apply_retention(db).await {
+        eprintln!("Failed to apply archive retention: {}", e);
+    }
+
+    progress.current_file = None;
+    progress.done = true;
+    let _ = progress.emit(app);
+    println!("Backfill finished: {} files imported", imported_files);
+
+    Ok(imported_files)
+}
+
+/// 初回起動時のみ、ログディレクトリ全体を取り込む
+pub fn spawn_initial_backfill(app: AppHandle, db: DB) {
+    tauri::async_runtime::spawn(async move {
+        if let Ok(Some(done)) = db.settings().get_setting(BACKFILL_DONE_KEY).await {
+            if done == "1" {
+                return;
+            }
+        }
+        match run_backfill(&app, &db).await {
+            Ok(_) => {
+                let _ = db.settings().set_setting(BACKFILL_DONE_KEY, "1").await;
+                // 既存のログは全て現在のパーサーで取り込み直したことになる
+                let _ = set_parser_version(&db).await;
+            }
+            Err(e) => eprintln!("Initial backfill failed: {}", e),
+        }
+    });
+}
+
+pub async fn get_parser_version(db: &DB) -> Result<ParserVersion, DbErr> {
+    // 未設定は parser_version を導入する前のデータ
+    let stored = db
+        .settings()
+        .get_setting(PARSER_VERSION_KEY)
+        .await?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1);
+    Ok(ParserVersion {
+        current: PARSER_VERSION,
+        stored,
+    })
+}
+
+async fn set_parser_version(db: &DB) -> Result<(), DbErr> {
+    db.settings()
+        .set_setting(PARSER_VERSION_KEY, &PARSER_VERSION.to_string())
+        .await
+}
+
+/// アーカイブ済みのログと、まだ残っている VRChat のログを現在のパーサーで解析し直す。
+/// hash が同じイベントは挿入されないので、新しく認識できるようになったイベントだけが増える
+pub async fn run_reparse(db: &DB, archive_dir: Option<&Path>) -> Result<ReparseResult, String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Backfill is already running".to_string());
+    }
+    let result = reparse(db, archive_dir).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+fn add_result(total: &mut ReparseResult, result: &ImportResult) {
+    total.files += 1;
+    total.inserted += result.inserted;
+    total.skipped += result.skipped;
+}
+
+async fn reparse(db: &DB, archive_dir: Option<&Path>) -> Result<ReparseResult, String> {
+    let mut total = ReparseResult::default();
+
+    // 1. アーカイブ (一時ファイルに展開してから取り込む)
+    let archives = db
+        .archived_logs()
+        .get_archives()
+        .await
+        .map_err(|e| e.to_string())?;
+    let temp_dir = archive_dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir);
+
+    for archive in &archives {
+        let temp = temp_dir.join(format!("{}.reparse", archive.file_name));
+        println!("Reparsing: {}", archive.archive_path);
+        let src = PathBuf::from(&archive.archive_path);
+        let dest = temp.clone();
+        let decompressed =
+            tauri::async_runtime::spawn_blocking(move || archiver::decompress_file(&src, &dest))
+                .await
+                .map_err(|e| io::Error::other(e.to_string()))
+                .and_then(|r| r);
+        // アーカイブした時と同じオフセットで変換する (旅行先などで再解析しても時刻がずれない)
+        let utc_offset = archive.utc_offset.and_then(FixedOffset::east_opt);
+        let utc_offsets = UtcOffsetRanges::from_json(archive.utc_offsets.as_deref());
+        let result = match decompressed {
+            Ok(_) => import_log_file(db, &temp, utc_offset, utc_offsets).await,
+            Err(e) => Err(e),
+        };
+        let _ = std::fs::remove_file(&temp);
+        match result {
+            Ok(result) => add_result(&mut total, &result),
+            Err(e) => eprintln!("  -> Error processing archive: {}", e),
+        }
+    }
+
+    // 2. VRChat のログディレクトリに残っているファイル (アーカイブ済みのものは除く)
+    for path in finished_log_files(db).await.unwrap_or_default() {
+        let Ok((size, content_hash)) = file_fingerprint_blocking(&path).await else {
+            continue;
+        };
+        if archives.iter().any(|a| a.content_hash == content_hash) {
+            continue;
+        }
+        println!("Reparsing: {:?}", path);
+        let path_str = path.to_string_lossy().to_string();
+        let utc_offset = db
+            .imported_files()
+            .get_utc_offset(&path_str)
+            .await
+            .ok()
+            .flatten()
+            .and_then(FixedOffset::east_opt);
+        let utc_offsets = saved_utc_offsets(db, &path_str).await;
+        match import_log_file(db, &path, utc_offset, utc_offsets).await {
+            Ok(result) => {
+                add_result(&mut total, &result);
+                let _ = db
+                    .imported_files()
+                    .mark_imported(
+                        &path_str,
+                        size,
+                        content_hash,
+                        result.utc_offset,
+                        result.utc_offsets,
+                    )
+                    .await;
+            }
+            Err(e) => eprintln!("  -> Error processing file: {}", e),
+        }
+    }
+
+    set_parser_version(db).await.map_err(|e| e.to_string())?;
+    println!(
+        "Reparse finished: {} files, {} new events",
+        total.files, total.inserted
+    );
+
+    Ok(total)
+}
@@ -1,6 +1,7 @@
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
     routing::get,
     Json, Router,
 };
@@ -8,10 +9,16 @@ use serde::Deserialize;
 use std::net::SocketAddr;
 use std::sync::Mutex;
 use tauri::async_runtime::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
 
+use crate::db::repositories::screenshots::Screenshot;
 use crate::db::DB;
+use crate::utils::hash::HASH_VERSION;
+use crate::utils::logdir;
 
+use super::screenshots;
+use super::shutdown::join_with_timeout;
 use super::watcher::LogPayload;
 
 pub const SERVER_PORT: u16 = 8727;
@@ -20,17 +27,30 @@ pub struct HttpSrv {
     pub handle: Mutex<Option<JoinHandle<()>>>,
     pub port: Mutex<u16>,
     pub running: Mutex<bool>,
+    /// アプリ全体の終了シグナル
+    shutdown: CancellationToken,
+    /// 現在のサーバーだけを止めるためのシグナル (ポート変更時の再起動用)
+    server_token: Mutex<CancellationToken>,
 }
 
 impl HttpSrv {
-    pub fn new(db: DB) -> Self {
-        let handle = spawn_server(db.clone());
+    pub fn new(db: DB, shutdown: CancellationToken) -> Self {
+        let server_token = shutdown.child_token();
+        let handle = spawn_server(db.clone(), server_token.clone());
         Self {
             handle: Mutex::new(Some(handle)),
             port: Mutex::new(SERVER_PORT),
             running: Mutex::new(false),
+            shutdown,
+            server_token: Mutex::new(server_token),
         }
     }
+
+    /// 終了時に、graceful shutdown の完了を待つためのハンドルを取り出す
+    pub fn take_handle(&self) -> Option<JoinHandle<()>> {
+        self.handle.lock().unwrap().take()
+    }
+
     pub async fn restart(&self, db: DB, new_port: u16) -> Result<(), String> {
         // 1. Save new port to DB first so spawn_server can read it
         db.settings()
@@ -38,17 +58,19 @@ impl HttpSrv {
             .await
             .map_err(|e| format!("Failed to save new port to DB: {}", e))?;
 
-        // 2. Kill the old server task if it exists
-        if let Some(handle) = self.handle.lock().unwrap().take() {
-            handle.abort();
-            println!("Old HTTP server task aborted.");
+        // 2. Stop the old server gracefully (in-flight requests are completed)
+        self.server_token.lock().unwrap().cancel();
+        if let Some(handle) = self.take_handle() {
+            join_with_timeout("Old HTTP server", handle).await;
         }
 
         // 3. Update the in-memory port
         *self.port.lock().unwrap() = new_port;
 
         // 4. Spawn a new server and save the new handle
-        let new_handle = spawn_server(db);
+        let server_token = self.shutdown.child_token();
+        let new_handle = spawn_server(db, server_token.clone());
+        *self.server_token.lock().unwrap() = server_token;
         *self.handle.lock().unwrap() = Some(new_handle);
         Ok(())
     }
@@ -63,17 +85,25 @@ struct LogParams {
     end: Option<i64>,
 }
 
+/// Response header carrying the hash version of the returned logs.
+/// When it changes, the same events come back with different hashes, so clients
+/// that dedup by hash (the mobile app) must drop their copy and sync again from scratch.
+const HASH_VERSION_HEADER: &str = "x-vrcp-hash-version";
+
 /// Handler for GET /logs
 async fn handle_get_logs(
     State(db): State<DB>,
     Query(params): Query<LogParams>,
-) -> Result<Json<Vec<LogPayload>>, StatusCode> {
+) -> Result<impl IntoResponse, StatusCode> {
     match db
         .logs()
         .get_session_expanded_logs(params.start.as_ref(), params.end.as_ref())
         .await
     {
-        Ok(logs) => Ok(Json(logs)),
+        Ok(logs) => Ok((
+            [(HASH_VERSION_HEADER, HASH_VERSION.to_string())],
+            Json::<Vec<LogPayload>>(logs),
+        )),
         Err(e) => {
             eprintln!("Failed to fetch logs from DB: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -81,8 +111,88 @@ async fn handle_get_logs(
     }
 }
 
-/// Start the HTTP server in a background task
-pub fn spawn_server(db: DB) -> JoinHandle<()> {
+/// Query parameters for the /screenshots endpoint (a session's startTime / endTime)
+#[derive(Deserialize)]
+struct ScreenshotParams {
+    start: i64,
+    end: i64,
+}
+
+/// Handler for GET /screenshots
+async fn handle_get_screenshots(
+    State(db): State<DB>,
+    Query(params): Query<ScreenshotParams>,
+) -> Result<Json<Vec<Screenshot>>, StatusCode> {
+    match db
+        .screenshots()
+        .get_screenshots(params.start, params.end)
+        .await
+    {
+        Ok(screenshots) => Ok(Json(screenshots)),
+        Err(e) => {
+            eprintln!("Failed to fetch screenshots from DB: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Handler for GET /screenshots/{id}/image
+/// Only images registered in the screenshots table and inside the screenshot folders can be read.
+async fn handle_get_screenshot_image(
+    State(db): State<DB>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let screenshot = match db.screenshots().get_screenshot(id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            eprintln!("Failed to fetch screenshot from DB: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // The stored path came from a log line, so only serve images that really are
+    // inside the screenshot folders (checked again on the resolved path)
+    let image = tauri::async_runtime::spawn_blocking(move || {
+        let dirs = logdir::find_screenshot_dir_candidates();
+        let path =
+            screenshots::confine_to_screenshot_dirs(std::path::Path::new(&screenshot.path), &dirs)?;
+        let bytes = std::fs::read(path).ok()?;
+        let content_type = screenshots::image_content_type(&bytes)?;
+        Some((content_type, bytes))
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (content_type, bytes) = image.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(([(header::CONTENT_TYPE, content_type)], bytes))
+}
+
+/// Routes served to the mobile app
+fn router(db: DB) -> Router {
+    let cors = CorsLayer::new()
+        .allow_origin([
+            "http://localhost"
+                .parse::<axum::http::HeaderValue>()
+                .unwrap(),
+            "http://localhost:8081"
+                .parse::<axum::http::HeaderValue>()
+                .unwrap(),
+        ])
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    Router::new()
+        .route("/logs", get(handle_get_logs))
+        .route("/screenshots", get(handle_get_screenshots))
+        .route("/screenshots/{id}/image", get(handle_get_screenshot_image))
+        .with_state(db) // Share the DB instance with handlers
+        .layer(cors) // Restrict CORS instead of permissive
+}
+
+/// Start the HTTP server in a background task.
+/// The server stops gracefully when `token` is cancelled.
+pub fn spawn_server(db: DB, token: CancellationToken) -> JoinHandle<()> {
     tauri::async_runtime::spawn(async move {
         // Read port from settings
         let port_str = db.settings().get_setting("port").await.unwrap_or(None);
@@ -91,28 +201,123 @@ pub fn spawn_server(db: DB) -> JoinHandle<()> {
             .unwrap_or(&SERVER_PORT.to_string())
             .parse()
             .unwrap_or(SERVER_PORT);
-        let cors = CorsLayer::new()
-            .allow_origin([
-                "http://localhost"
-                    .parse::<axum::http::HeaderValue>()
-                    .unwrap(),
-                "http://localhost:8081"
-                    .parse::<axum::http::HeaderValue>()
-                    .unwrap(),
-            ])
-            .allow_methods(Any)
-            .allow_headers(Any);
-
-        let app = Router::new()
-            .route("/logs", get(handle_get_logs))
-            .with_state(db) // Share the DB instance with handlers
-            .layer(cors); // Restrict CORS instead of permissive
+        let app = router(db);
 
         // Listen on 0.0.0.0 to accept connections from LAN (Mobile)
         let addr = SocketAddr::from(([0, 0, 0, 0], port));
         println!("HTTP Server listening on http://{}", addr);
 
         let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-        axum::serve(listener, app).await.unwrap();
+        axum::serve(listener, app)
+            .with_graceful_shutdown(token.cancelled_owned())
+            .await
+            .unwrap();
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::backfill::import_log_file;
+    use crate::utils::date::{naive_to_i64, resolve_local_offset};
+    use chrono::NaiveDateTime;
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    fn log_start() -> NaiveDateTime {
+        NaiveDateTime::parse_from_str("2026-10-18 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    /// Writes a log of `lines` lines (ten per second), half of which are events
+    fn write_large_log(path: &std::path::Path, lines: usize) {
+        let start = log_start();
+        let mut w = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        for i in 0..lines {
+            let time =
+                (start + chrono::Duration::seconds(i as i64 / 10)).format("%Y.%m.%d %H:%M:%S");
+            if i % 2 == 0 {
+                writeln!(
+                    w,
+                    "{} Log        -  [Behaviour] OnPlayerJoined Player{} (usr_{:08})",
+                    time, i, i
+                )
+            } else {
+                writeln!(
+                    w,
+                    "{} Debug      -  [Network Processing] RPC for unrelated object {}",
+                    time, i
+                )
+            }
+            .unwrap();
+        }
+        w.flush().unwrap();
+    }
+
+    /// /logs must keep answering quickly while a large file is being imported,
+    /// i.e. reading and parsing must not run on the runtime that serves HTTP.
+    /// Each request asks for the minute the import is writing right now, so it
+    /// reads rows that are being inserted at the same time.
+    ///
+    /// This is a latency benchmark and depends on the machine, so it only runs on demand:
+    /// `cargo test -- --ignored logs_respond_quickly_during_a_large_backfill --nocapture`
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    #[ignore = "latency benchmark; run with --ignored"]
+    async fn logs_respond_quickly_during_a_large_backfill() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::new(dir.path().join("data")).await.unwrap();
+        let log_path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        write_large_log(&log_path, 100_000);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/logs", listener.local_addr().unwrap());
+        let app = router(db.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let backfill_db = db.clone();
+        let backfill = tokio::spawn(async move {
+            import_log_file(&backfill_db, &log_path, None, Default::default()).await
+        });
+
+        // Follow the import: the next window starts at the newest row seen so far
+        const WINDOW_MS: i64 = 60_000;
+        let start = log_start();
+        let mut from = naive_to_i64(&start, resolve_local_offset(&start));
+        let mut rows_read = 0;
+        let client = reqwest::Client::new();
+        let mut latencies = Vec::new();
+        while !backfill.is_finished() {
+            let url = format!("{}?start={}&end={}", base, from, from + WINDOW_MS);
+            let started = Instant::now();
+            let res = client.get(&url).send().await.unwrap();
+            assert!(res.status().is_success());
+            let body = res.bytes().await.unwrap();
+            latencies.push(started.elapsed());
+
+            let logs: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            rows_read += logs.len();
+            if let Some(newest) = logs.last().and_then(|l| l["timestamp"].as_i64()) {
+                from = newest;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let imported = backfill.await.unwrap().unwrap();
+        assert_eq!(imported.inserted, 50_000);
+
+        assert!(
+            latencies.len() >= 5,
+            "backfill finished before /logs could be measured"
+        );
+        assert!(rows_read > 0, "/logs never saw the rows being imported");
+        latencies.sort();
+        let p95 = latencies[latencies.len() * 95 / 100];
+        let max = latencies[latencies.len() - 1];
+        println!(
+            "/logs during backfill: {} requests ({} rows), p95 {:?}, max {:?}",
+            latencies.len(),
+            rows_read,
+            p95,
+            max
+        );
+        assert!(p95 < Duration::from_millis(200), "p95 latency {:?}", p95);
+    }
+}
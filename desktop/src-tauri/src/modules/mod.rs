@@ -1,6 +1,11 @@
 // src-tauri/src/modules/mod.rs
 
+pub mod archiver;
+pub mod backfill;
 pub mod http;
+pub mod process;
+pub mod screenshots;
+pub mod shutdown;
 pub mod systray;
 pub mod vrcapi;
 pub mod watcher;
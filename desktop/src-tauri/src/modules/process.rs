@@ -0,0 +1,278 @@
+use crate::db::repositories::process_samples::ProcessSample;
+use crate::db::DB;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::AppHandle;
+use tauri_specta::Event;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio_util::sync::CancellationToken;
+
+/// Windows でも Proton/Wine でも、ゲーム本体のプロセス名はこれになる
+const VRCHAT_EXE: &str = "VRChat.exe";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// リソース使用量を process_samples に記録する間隔
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+/// process_samples を残す期間
+const SAMPLE_RETENTION_DAYS: i64 = 90;
+/// 古いサンプルを消す間隔
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// pid ごとの、そのプロセスが書いているログのアカウント (watcher がログから判定して更新する)
+pub type ProcessAccounts = Arc<RwLock<HashMap<u32, String>>>;
+
+/// VRChat のプロセスの起動・終了 (ログの AppStart/AppStop より正確な時刻が分かる)
+#[derive(Clone, Debug, Serialize, Deserialize, Type, Event)]
+pub struct VrcProcessChanged {
+    pub pid: u32,
+    pub running: bool,
+    /// 起動時はプロセスの開始時刻、終了時は終了を検知した時刻
+    pub timestamp: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct VrcProcess {
+    pub pid: u32,
+    /// プロセスの開始時刻 (UTC ミリ秒)
+    pub started_at: i64,
+}
+
+#[derive(Clone, Debug)]
+pub enum ProcessChange {
+    Started(VrcProcess),
+    Stopped {
+        process: VrcProcess,
+        stopped_at: i64,
+    },
+}
+
+/// 1回の確認の結果
+#[derive(Clone, Debug)]
+pub struct ProcessUpdate {
+    /// 現在起動している VRChat のプロセス
+    pub running: Vec<VrcProcess>,
+    pub changes: Vec<ProcessChange>,
+}
+
+pub struct ProcessMonitor {
+    system: System,
+    running: HashMap<Pid, VrcProcess>,
+}
+
+impl ProcessMonitor {
+    /// プロセス一覧を取得できない OS では監視しない (ログの行だけで判定する)
+    pub fn is_supported() -> bool {
+        sysinfo::IS_SUPPORTED_SYSTEM
+    }
+
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            running: HashMap::new(),
+        }
+    }
+
+    /// プロセス一覧を取り直し、前回からの起動・終了を返す (同期処理)
+    pub fn poll(&mut self) -> ProcessUpdate {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_cpu()
+                .with_memory()
+                .with_tasks(),
+        );
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let found: HashMap<Pid, VrcProcess> = self
+            .system
+            .processes()
+            .iter()
+            .filter(|(_, p)| is_vrchat(p.name(), p.cmd()))
+            .map(|(pid, p)| {
+                (
+                    *pid,
+                    VrcProcess {
+                        pid: pid.as_u32(),
+                        started_at: p.start_time() as i64 * 1000,
+                    },
+                )
+            })
+            .collect();
+
+        let mut changes: Vec<ProcessChange> = self
+            .running
+            .iter()
+            .filter(|(pid, _)| !found.contains_key(pid))
+            .map(|(_, process)| ProcessChange::Stopped {
+                process: process.clone(),
+                stopped_at: now,
+            })
+            .collect();
+        changes.extend(
+            found
+                .iter()
+                .filter(|(pid, _)| !self.running.contains_key(pid))
+                .map(|(_, process)| ProcessChange::Started(process.clone())),
+        );
+
+        self.running = found;
+        ProcessUpdate {
+            running: self.running.values().cloned().collect(),
+            changes,
+        }
+    }
+
+    /// 直前の poll の時点での、起動中の VRChat のリソース使用量。
+    /// CPU 使用率は前回の poll からの平均なので、2回目以降の poll の後で取る
+    pub fn samples(&self, timestamp: i64, accounts: &HashMap<u32, String>) -> Vec<ProcessSample> {
+        self.running
+            .keys()
+            .filter_map(|pid| self.system.process(*pid))
+            .map(|p| ProcessSample {
+                id: 0,
+                pid: p.pid().as_u32() as i32,
+                timestamp,
+                cpu_usage: p.cpu_usage() as f64,
+                memory_bytes: p.memory() as i64,
+                // スレッド一覧は Linux でしか取れない
+                thread_count: p.tasks().map(|t| t.len() as i32),
+                account: accounts.get(&p.pid().as_u32()).cloned(),
+            })
+            .collect()
+    }
+}
+
+impl Default for ProcessMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Proton/Wine では実行ファイルが wine-preloader になるため、
+/// プロセス名 (comm) か、Windows のパスのままの argv[0] で判定する
+fn is_vrchat(name: &OsStr, cmd: &[OsString]) -> bool {
+    let is_exe = |s: &OsStr| {
+        s.to_string_lossy()
+            .rsplit(['/', '\\'])
+            .next()
+            .is_some_and(|n| n.eq_ignore_ascii_case(VRCHAT_EXE))
+    };
+    is_exe(name) || cmd.first().is_some_and(|arg| is_exe(arg))
+}
+
+/// SAMPLE_RETENTION_DAYS より古いサンプルを消し、消した件数を返す
+pub async fn apply_sample_retention(db: &DB) -> Result<u64, String> {
+    let cutoff = chrono::Utc::now().timestamp_millis() - SAMPLE_RETENTION_DAYS * DAY_MS;
+    db.process_samples()
+        .delete_before(cutoff)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// VRChat の起動・終了を監視する。変化があった時 (と最初の確認時) に ProcessUpdate を送る。
+/// 起動中は SAMPLE_INTERVAL ごとにリソース使用量を、accounts で分かるアカウントと共に DB に記録する。
+/// 監視できない環境では何も送らずに終わる
+pub fn spawn_process_monitor(
+    app: AppHandle,
+    db: DB,
+    accounts: ProcessAccounts,
+    shutdown: CancellationToken,
+) -> UnboundedReceiver<ProcessUpdate> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    if !ProcessMonitor::is_supported() {
+        println!("Process monitoring is not supported on this platform.");
+        return rx;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut monitor = ProcessMonitor::new();
+        let mut first = true;
+        let mut last_sample: Option<Instant> = None;
+        let mut last_retention: Option<Instant> = None;
+
+        while !shutdown.is_cancelled() {
+            // プロセス一覧の取得は同期 I/O なので blocking スレッドで行う
+            let polled = tauri::async_runtime::spawn_blocking(move || {
+                let update = monitor.poll();
+                (monitor, update)
+            })
+            .await;
+            let Ok((returned, update)) = polled else {
+                eprintln!("Process monitor failed");
+                break;
+            };
+            monitor = returned;
+
+            for change in &update.changes {
+                let event = match change {
+                    ProcessChange::Started(p) => {
+                        println!("VRChat process started: pid {}", p.pid);
+                        VrcProcessChanged {
+                            pid: p.pid,
+                            running: true,
+                            timestamp: p.started_at,
+                        }
+                    }
+                    ProcessChange::Stopped {
+                        process,
+                        stopped_at,
+                    } => {
+                        println!("VRChat process stopped: pid {}", process.pid);
+                        VrcProcessChanged {
+                            pid: process.pid,
+                            running: false,
+                            timestamp: *stopped_at,
+                        }
+                    }
+                };
+                let _ = event.emit(&app);
+            }
+
+            // 起動直後は CPU 使用率の基準が無いため、次の poll から記録する
+            let started = update
+                .changes
+                .iter()
+                .any(|c| matches!(c, ProcessChange::Started(_)));
+            if update.running.is_empty() || started {
+                last_sample = None;
+            } else if last_sample.is_none_or(|t| t.elapsed() >= SAMPLE_INTERVAL) {
+                last_sample = Some(Instant::now());
+                let now = chrono::Utc::now().timestamp_millis();
+                let samples = match accounts.read() {
+                    Ok(accounts) => monitor.samples(now, &accounts),
+                    Err(_) => monitor.samples(now, &HashMap::new()),
+                };
+                if let Err(e) = db.process_samples().insert_samples(&samples).await {
+                    eprintln!("Failed to record process samples: {}", e);
+                }
+            }
+
+            if last_retention.is_none_or(|t| t.elapsed() >= RETENTION_INTERVAL) {
+                last_retention = Some(Instant::now());
+                if let Err(e) = apply_sample_retention(&db).await {
+                    eprintln!("Failed to apply process sample retention: {}", e);
+                }
+            }
+
+            if first || !update.changes.is_empty() {
+                first = false;
+                if tx.send(update).is_err() {
+                    break;
+                }
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,549 @@
+use crate::cmds::vrclog::sessions::{build_sessions, Interval};
+use crate::db::repositories::screenshots::{screenshot_file_name, Screenshot, ScreenshotMetadata};
+use crate::db::DB;
+use crate::modules::watcher::{LogPayload, VrcLogEvent};
+use crate::utils::date::{naive_to_i64, resolve_local_offset};
+use crate::utils::{logdir, png};
+use chrono::NaiveDateTime;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// VRChat が XMP を書き込む iTXt のキーワード
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+/// VRCX などが JSON を書き込むキーワード (書き込みもこの形式に合わせる)
+const DESCRIPTION_KEYWORD: &str = "Description";
+const APPLICATION: &str = "vrcp";
+/// ログに出た時点ではまだ書き込み中のことがあるため、少し待ってから読む
+const LOGGED_SCREENSHOT_DELAY: Duration = Duration::from_secs(3);
+
+/// 保存先の直下 (古いバージョン) と、YYYY-MM のサブフォルダの画像を列挙する
+fn list_screenshots(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.is_dir() {
+            if let Ok(month) = fs::read_dir(&path) {
+                files.extend(
+                    month
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| is_screenshot(p)),
+                );
+            }
+        } else if is_screenshot(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn is_screenshot(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.starts_with("VRChat_") && has_image_extension(path)
+}
+
+fn has_image_extension(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    matches!(ext.as_deref(), Some("png" | "jpg" | "jpeg"))
+}
+
+/// 保存先のフォルダ (dirs は canonicalize 済み) の中にある画像ファイルなら、その実体のパスを返す。
+/// ログの行はワールドからも書き込めるため、ログに出たパスや DB のパスをそのまま開かない
+pub fn confine_to_screenshot_dirs(path: &Path, dirs: &[PathBuf]) -> Option<PathBuf> {
+    if !has_image_extension(path) {
+        return None;
+    }
+    // シンボリックリンクや .. で外を指していても、実体のパスで確かめる
+    let canonical = fs::canonicalize(path).ok()?;
+    let inside = dirs.iter().any(|dir| canonical.starts_with(dir));
+    (inside && canonical.is_file() && has_image_extension(&canonical)).then_some(canonical)
+}
+
+/// 先頭のシグネチャから PNG / JPEG の Content-Type を決める (どちらでもなければ None)
+pub fn image_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if png::has_signature(bytes) {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else {
+        None
+    }
+}
+
+/// VRChat_YYYY-MM-DD_HH-MM-SS.mmm_WxH.png のファイル名に埋め込まれた撮影時刻 (ローカル時間)
+fn file_name_time(path: &Path) -> Option<NaiveDateTime> {
+    let name = path.file_stem()?.to_str()?.strip_prefix("VRChat_")?;
+    let ts = name.split('_').take(2).collect::<Vec<_>>().join("_");
+    NaiveDateTime::parse_from_str(&ts, "%Y-%m-%d_%H-%M-%S%.f").ok()
+}
+
+/// 撮影時刻 (UTC ミリ秒)。ファイル名から取れなければ更新日時を使う
+fn taken_at(path: &Path) -> Option<i64> {
+    if let Some(ndt) = file_name_time(path) {
+        return Some(naive_to_i64(&ndt, resolve_local_offset(&ndt)));
+    }
+    let mtime = path.metadata().and_then(|m| m.modified()).ok()?;
+    Some(chrono::DateTime::<chrono::Utc>::from(mtime).timestamp_millis())
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// <vrc:WorldID>..</vrc:WorldID> と vrc:WorldID="..." のどちらの書き方でも値を取り出す
+fn xmp_value(xmp: &str, tag: &str) -> Option<String> {
+    static VALUE_RE: OnceLock<Regex> = OnceLock::new();
+    let re = VALUE_RE
+        .get_or_init(|| Regex::new(r#"<([\w:]+)>([^<]*)</([\w:]+)>|([\w:]+)="([^"]*)""#).unwrap());
+
+    let value = re.captures_iter(xmp).find_map(|caps| match caps.get(1) {
+        Some(open) => {
+            let closed = caps.get(3).is_some_and(|c| c.as_str() == tag);
+            (open.as_str() == tag && closed).then(|| caps.get(2))?
+        }
+        None => (caps.get(4)?.as_str() == tag).then(|| caps.get(5))?,
+    })?;
+    let value = value.as_str().trim();
+    (!value.is_empty()).then(|| unescape_xml(value))
+}
+
+fn parse_xmp(xmp: &str) -> Option<ScreenshotMetadata> {
+    static PLAYERS_RE: OnceLock<Regex> = OnceLock::new();
+    static LI_RE: OnceLock<Regex> = OnceLock::new();
+    let players_re =
+        PLAYERS_RE.get_or_init(|| Regex::new(r"(?s)<vrc:Players>(.*?)</vrc:Players>").unwrap());
+    let li_re = LI_RE.get_or_init(|| Regex::new(r"<rdf:li[^>]*>([^<]*)</rdf:li>").unwrap());
+
+    let players = players_re
+        .captures(xmp)
+        .map(|caps| {
+            li_re
+                .captures_iter(&caps[1])
+                .map(|li| unescape_xml(li[1].trim()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let metadata = ScreenshotMetadata {
+        world_id: xmp_value(xmp, "vrc:WorldID"),
+        world_name: xmp_value(xmp, "vrc:WorldDisplayName"),
+        instance_id: xmp_value(xmp, "vrc:InstanceID"),
+        author: xmp_value(xmp, "xmp:Author").or_else(|| xmp_value(xmp, "vrc:Author")),
+        players,
+        stamped: false,
+    };
+    (metadata.world_id.is_some() || metadata.author.is_some()).then_some(metadata)
+}
+
+/// VRCX 形式の JSON ({ author, world: { id, name, instanceId }, players: [{ displayName }] })
+fn parse_description(text: &str) -> Option<ScreenshotMetadata> {
+    let v: Value = serde_json::from_str(text).ok()?;
+    let world = v.get("world")?;
+    let str_of = |v: &Value, key: &str| v.get(key).and_then(|s| s.as_str()).map(str::to_owned);
+
+    Some(ScreenshotMetadata {
+        world_id: str_of(world, "id"),
+        world_name: str_of(world, "name"),
+        instance_id: str_of(world, "instanceId"),
+        author: v.get("author").and_then(|a| str_of(a, "displayName")),
+        players: v
+            .get("players")
+            .and_then(|p| p.as_array())
+            .map(|players| {
+                players
+                    .iter()
+                    .filter_map(|p| str_of(p, "displayName"))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        stamped: str_of(&v, "application").as_deref() == Some(APPLICATION),
+    })
+}
+
+/// PNG のテキストチャンクからメタデータを読む。両方あれば、プレイヤーを含む JSON の方を優先する
+fn read_metadata(path: &Path) -> Option<ScreenshotMetadata> {
+    let chunks = png::read_text_chunks(path).ok()?;
+    let description = chunks
+        .iter()
+        .filter(|c| c.keyword == DESCRIPTION_KEYWORD)
+        .find_map(|c| parse_description(&c.text));
+    let xmp = chunks
+        .iter()
+        .filter(|c| c.keyword == XMP_KEYWORD)
+        .find_map(|c| parse_xmp(&c.text));
+
+    match (description, xmp) {
+        (Some(mut d), Some(x)) => {
+            d.world_id = d.world_id.or(x.world_id);
+            d.world_name = d.world_name.or(x.world_name);
+            d.instance_id = d.instance_id.or(x.instance_id);
+            d.author = d.author.or(x.author);
+            if d.players.is_empty() {
+                d.players = x.players;
+            }
+            Some(d)
+        }
+        (d, x) => d.or(x),
+    }
+}
+
+/// 撮影時刻を含むセッションから、ワールド・インスタンス・その時点でいたプレイヤーを復元する。
+/// 複数クライアントを同時に起動していた場合は、撮影したアカウントのセッションを使う
+/// (アカウントが分からず、どのセッションか決められない場合は書き込まない)
+async fn session_metadata(
+    db: &DB,
+    taken_at: i64,
+    account: Option<&str>,
+) -> Option<ScreenshotMetadata> {
+    let logs = db
+        .logs()
+        .get_session_expanded_logs(Some(&taken_at), Some(&taken_at))
+        .await
+        .ok()?;
+    let mut candidates = build_sessions(logs, taken_at).into_iter().filter(|s| {
+        s.start_time <= taken_at
+            && taken_at <= s.end_time
+            && account.is_none_or(|account| s.account.as_deref() == Some(account))
+    });
+    let session = candidates.next()?;
+    if account.is_none() && candidates.next().is_some() {
+        return None;
+    }
+
+    let present = |intervals: &[Interval]| {
+        intervals
+            .iter()
+            .any(|i| i.start <= taken_at && taken_at <= i.end)
+    };
+    // 同じ名前は、最初に出た順のまま1つにする (並び替えると自分が先頭でなくなる)
+    let mut seen = HashSet::new();
+    let players: Vec<String> = session
+        .local_player
+        .iter()
+        .chain(session.players.iter())
+        .filter(|p| present(&p.intervals))
+        .map(|p| p.name.clone())
+        .filter(|name| seen.insert(name.clone()))
+        .collect();
+
+    Some(ScreenshotMetadata {
+        world_id: session.instance.as_ref().map(|i| i.world_id.clone()),
+        world_name: Some(session.world_name),
+        instance_id: Some(session.instance_id),
+        author: session.username,
+        players,
+        stamped: true,
+    })
+}
+
+/// メタデータを iTXt チャンクとして追記する (画像データは再エンコードしない)
+fn stamp(path: &Path, metadata: &ScreenshotMetadata) -> std::io::Result<()> {
+    let description = json!({
+        "application": APPLICATION,
+        "version": 1,
+        "author": { "displayName": metadata.author },
+        "world": {
+            "id": metadata.world_id,
+            "name": metadata.world_name,
+            "instanceId": metadata.instance_id,
+        },
+        "players": metadata
+            .players
+            .iter()
+            .map(|name| json!({ "displayName": name }))
+            .collect::<Vec<_>>(),
+    });
+    png::append_itxt(path, DESCRIPTION_KEYWORD, &description.to_string())
+}
+
+/// PNG のメタデータを読んで記録する。無ければ、stamping が有効ならセッションから復元して書き込む。
+/// メタデータを記録できたら true
+async fn index_metadata(db: &DB, screenshot: &Screenshot, stamping: bool) -> Result<bool, String> {
+    // 書き込むこともあるので、保存先の外のファイル (以前のバージョンで記録した行など) は扱わない
+    let Some(path) = confine_to_screenshot_dirs(
+        Path::new(&screenshot.path),
+        &logdir::find_screenshot_dir_candidates(),
+    ) else {
+        return Ok(false);
+    };
+    let is_png = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    if !is_png {
+        return Ok(false);
+    }
+
+    let read_path = path.clone();
+    let found = tauri::async_runtime::spawn_blocking(move || read_metadata(&read_path))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let metadata = match found {
+        Some(metadata) => metadata,
+        None if stamping => {
+            let Some(metadata) =
+                session_metadata(db, screenshot.taken_at, screenshot.account.as_deref()).await
+            else {
+                return Ok(false);
+            };
+            let to_write = metadata.clone();
+            tauri::async_runtime::spawn_blocking(move || stamp(&path, &to_write))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| format!("Failed to stamp {:?}: {}", screenshot.path, e))?;
+            println!("Stamped metadata into {:?}", screenshot.path);
+            metadata
+        }
+        None => return Ok(false),
+    };
+
+    db.screenshots()
+        .set_metadata(screenshot.id, &metadata)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// メタデータをまだ読めていないスクリーンショットを全て処理する
+async fn index_pending_metadata(db: &DB) -> Result<(), String> {
+    let stamping = db
+        .settings()
+        .get_screenshot_stamping()
+        .await
+        .map_err(|e| e.to_string())?;
+    let pending = db
+        .screenshots()
+        .get_without_metadata()
+        .await
+        .map_err(|e| e.to_string())?;
+    for screenshot in pending {
+        // 1枚読めなくても残りは続ける
+        if let Err(e) = index_metadata(db, &screenshot, stamping).await {
+            eprintln!("Failed to index screenshot metadata: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// ログに出たパスを、この PC で開けるパスにする。
+/// Proton では C:\users\steamuser\Pictures\VRChat\YYYY-MM\... のように記録されるため、
+/// 保存先の候補から同じ YYYY-MM/ファイル名 を探す。保存先の外のファイルは記録しない
+fn resolve_local_path(logged: &str) -> Option<PathBuf> {
+    let dirs = logdir::find_screenshot_dir_candidates();
+    if let Some(path) = confine_to_screenshot_dirs(Path::new(logged), &dirs) {
+        return Some(path);
+    }
+    let mut parts = logged.rsplit(['/', '\\']);
+    let file_name = parts.next()?;
+    let month = parts.next();
+
+    dirs.iter()
+        .flat_map(|dir| {
+            let nested = month.map(|m| dir.join(m).join(file_name));
+            nested.into_iter().chain([dir.join(file_name)])
+        })
+        .find_map(|p| confine_to_screenshot_dirs(&p, &dirs))
+}
+
+/// ログの Screenshot イベントから、この PC にあるファイルを探して記録する。
+/// 記録したパス (見つからなければ None) を返す
+pub async fn record_logged(
+    db: &DB,
+    logged_path: &str,
+    taken_at: i64,
+    account: Option<&str>,
+) -> Result<Option<String>, String> {
+    let logged = logged_path.to_owned();
+    let resolved = tauri::async_runtime::spawn_blocking(move || resolve_local_path(&logged))
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(path) = resolved else {
+        return Ok(None);
+    };
+    let path_str = path.to_string_lossy().to_string();
+    db.screenshots()
+        .upsert_local(&path_str, taken_at, account)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(path_str))
+}
+
+/// 取り込んだログの Screenshot イベントを記録する (バックフィル・再解析用)。
+/// ログが取り込み済みでも、スクリーンショットの記録は漏れなく行う
+pub async fn record_logged_payloads(db: &DB, payloads: &[LogPayload]) {
+    for payload in payloads {
+        if let VrcLogEvent::Screenshot { path } = &payload.event {
+            if let Err(e) =
+                record_logged(db, path, payload.timestamp, payload.account.as_deref()).await
+            {
+                eprintln!("Failed to record screenshot: {}", e);
+            }
+        }
+    }
+}
+
+/// ログの Screenshot イベントを受けて、ファイルを探してメタデータを記録する
+pub fn spawn_index_logged(db: DB, logged_path: String, taken_at: i64, account: Option<String>) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(LOGGED_SCREENSHOT_DELAY).await;
+
+        let path_str = match record_logged(&db, &logged_path, taken_at, account.as_deref()).await {
+            Ok(Some(path)) => path,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("Failed to record screenshot: {}", e);
+                return;
+            }
+        };
+
+        let stamping = db
+            .settings()
+            .get_screenshot_stamping()
+            .await
+            .unwrap_or(false);
+        if let Ok(Some(screenshot)) = db
+            .screenshots()
+            .get_by_file_name(screenshot_file_name(&path_str))
+            .await
+        {
+            if let Err(e) = index_metadata(&db, &screenshot, stamping).await {
+                eprintln!("Failed to index screenshot metadata: {}", e);
+            }
+        }
+    });
+}
+
+/// 保存先のフォルダを走査し、ログに無いスクリーンショット (ログが消えた後の分など) も記録する。
+/// 新しく追加した件数を返す
+pub async fn scan_screenshots(db: &DB) -> Result<u32, String> {
+    // ディレクトリの走査は同期 I/O なので blocking スレッドで行う
+    let found = tauri::async_runtime::spawn_blocking(|| {
+        logdir::find_screenshot_dir_candidates()
+            .iter()
+            .flat_map(|dir| list_screenshots(dir))
+            .filter_map(|path| Some((taken_at(&path)?, path)))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut added = 0;
+    for (taken_at, path) in found {
+        if db
+            .screenshots()
+            .upsert_local(&path.to_string_lossy(), taken_at, None)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            added += 1;
+        }
+    }
+
+    index_pending_metadata(db).await?;
+    Ok(added)
+}
+
+/// 起動時にバックグラウンドで走査する
+pub fn spawn_screenshot_scan(db: DB) {
+    tauri::async_runtime::spawn(async move {
+        match scan_screenshots(&db).await {
+            Ok(added) => println!("Screenshot scan finished. ({} new)", added),
+            Err(e) => eprintln!("Screenshot scan failed: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_BYTES: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\0IEND";
+
+    #[test]
+    fn confines_paths_to_the_screenshot_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("VRChat");
+        fs::create_dir_all(dir.join("2026-10")).unwrap();
+        let dirs = vec![fs::canonicalize(&dir).unwrap()];
+
+        let inside = dir
+            .join("2026-10")
+            .join("VRChat_2026-10-18_12-00-00.000_1920x1080.png");
+        fs::write(&inside, PNG_BYTES).unwrap();
+        assert_eq!(
+            confine_to_screenshot_dirs(&inside, &dirs),
+            Some(fs::canonicalize(&inside).unwrap())
+        );
+
+        // 保存先の外、.. で外に出るパス、画像以外の拡張子
+        let outside = root.path().join("secret.png");
+        fs::write(&outside, PNG_BYTES).unwrap();
+        assert_eq!(confine_to_screenshot_dirs(&outside, &dirs), None);
+        let escaped = dir.join("2026-10").join("..").join("..").join("secret.png");
+        assert_eq!(confine_to_screenshot_dirs(&escaped, &dirs), None);
+        let text = dir.join("notes.txt");
+        fs::write(&text, "secret").unwrap();
+        assert_eq!(confine_to_screenshot_dirs(&text, &dirs), None);
+        assert_eq!(
+            confine_to_screenshot_dirs(&dir.join("missing.png"), &dirs),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_links_pointing_outside_the_screenshot_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("VRChat");
+        fs::create_dir(&dir).unwrap();
+        let dirs = vec![fs::canonicalize(&dir).unwrap()];
+
+        let outside = root.path().join("id_rsa");
+        fs::write(&outside, "secret").unwrap();
+        let link = dir.join("VRChat_2026-10-18_12-00-00.000_1920x1080.png");
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        assert_eq!(confine_to_screenshot_dirs(&link, &dirs), None);
+    }
+
+    #[test]
+    fn reads_xmp_values_written_as_elements_or_attributes() {
+        let xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF>
+            <rdf:Description xmp:Author="amgr &amp; co" vrc:WorldID="wrld_123">
+              <vrc:WorldDisplayName> Test World </vrc:WorldDisplayName>
+              <vrc:InstanceID></vrc:InstanceID>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#;
+        let metadata = parse_xmp(xmp).unwrap();
+        assert_eq!(metadata.author.as_deref(), Some("amgr & co"));
+        assert_eq!(metadata.world_id.as_deref(), Some("wrld_123"));
+        assert_eq!(metadata.world_name.as_deref(), Some("Test World"));
+        assert_eq!(metadata.instance_id, None);
+    }
+
+    #[test]
+    fn detects_the_image_type_from_the_signature() {
+        assert_eq!(image_content_type(PNG_BYTES), Some("image/png"));
+        assert_eq!(
+            image_content_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]),
+            Some("image/jpeg")
+        );
+        assert_eq!(image_content_type(b"[core]\nname = secret"), None);
+        assert_eq!(image_content_type(&[]), None);
+    }
+}
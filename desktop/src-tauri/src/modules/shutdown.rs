@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+
+use crate::Ctx;
+
+/// 各タスクの終了を待つ最大時間 (これを過ぎたら強制終了する)
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 終了処理を終えて、本当に終了してよい状態
+static FINISHED: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// タスクの終了を待つ。時間内に終わらなければ abort する
+pub async fn join_with_timeout(name: &str, mut handle: JoinHandle<()>) {
+    match tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut handle).await {
+        Ok(_) => println!("{} stopped.", name),
+        Err(_) => {
+            eprintln!("{} did not stop in time, aborting.", name);
+            handle.abort();
+        }
+    }
+}
+
+pub fn is_finished() -> bool {
+    FINISHED.load(Ordering::SeqCst)
+}
+
+/// 終了・再起動の共通処理。
+/// 各タスク (watcher, HTTP サーバー) に終了を通知し、
+/// watcher の書き込みバッファと読み込み位置の保存が終わってから終了する
+pub fn exit(app: &AppHandle, restart: bool) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Some(ctx) = app.try_state::<Ctx>() {
+            println!("Shutting down...");
+            ctx.shutdown.cancel();
+            if let Some(handle) = ctx.watcher.take_handle() {
+                join_with_timeout("Log watcher", handle).await;
+            }
+            if let Some(handle) = ctx.srv.take_handle() {
+                join_with_timeout("HTTP server", handle).await;
+            }
+        }
+
+        FINISHED.store(true, Ordering::SeqCst);
+        if restart {
+            app.restart();
+        } else {
+            app.exit(0);
+        }
+    });
+}
@@ -1,9 +1,23 @@
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
     AppHandle, Manager, Window, WindowEvent,
 };
+use tauri_plugin_dialog::DialogExt;
+
+use crate::modules::shutdown;
+use crate::modules::watcher::WatcherCommand;
+use crate::Ctx;
+
+/// ログ監視への操作をトレイから送る
+fn send_watcher_command(app: &AppHandle, command: WatcherCommand) {
+    if let Some(ctx) = app.try_state::<Ctx>() {
+        if let Err(e) = ctx.watcher.send(command) {
+            eprintln!("Failed to send watcher command: {}", e);
+        }
+    }
+}
 
 /// タスクトレイのセットアップ
 /// (アイコン表示、メニュー作成、クリックイベントの登録)
@@ -13,8 +27,49 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
     let separator = PredefinedMenuItem::separator(app)?;
     let restart_i = MenuItem::with_id(app, "restart", "Restart VRCP", true, None::<&str>)?;
     let quit_i = MenuItem::with_id(app, "quit", "Quit VRCP", true, None::<&str>)?;
+    // ログ監視の操作 (パーサーの変更を特定のログで確かめる時用)
+    let pause_i = MenuItem::with_id(app, "watcher_pause", "Pause", true, None::<&str>)?;
+    let resume_i = MenuItem::with_id(app, "watcher_resume", "Resume", true, None::<&str>)?;
+    let rescan_i = MenuItem::with_id(
+        app,
+        "watcher_rescan",
+        "Rescan Current Log",
+        true,
+        None::<&str>,
+    )?;
+    let switch_i = MenuItem::with_id(
+        app,
+        "watcher_switch",
+        "Watch Log File...",
+        true,
+        None::<&str>,
+    )?;
+    let follow_i = MenuItem::with_id(
+        app,
+        "watcher_follow",
+        "Follow Latest Logs",
+        true,
+        None::<&str>,
+    )?;
+    let watcher_separator = PredefinedMenuItem::separator(app)?;
+    let watcher_menu = Submenu::with_items(
+        app,
+        "Log Watcher",
+        true,
+        &[
+            &pause_i,
+            &resume_i,
+            &rescan_i,
+            &watcher_separator,
+            &switch_i,
+            &follow_i,
+        ],
+    )?;
     // メニュー配置
-    let menu = Menu::with_items(app, &[&show_i, &separator, &restart_i, &quit_i])?;
+    let menu = Menu::with_items(
+        app,
+        &[&show_i, &watcher_menu, &separator, &restart_i, &quit_i],
+    )?;
 
     // 2. アイコン画像の読み込み
     // modulesフォルダ内にあるため、iconsフォルダへは ../../ でアクセス
@@ -28,13 +83,29 @@ pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
         .on_menu_event(|app, event| {
             match event.id.as_ref() {
                 "quit" => {
-                    // 終了する
-                    app.exit(0);
+                    // 書き込みを終えてから終了する
+                    shutdown::exit(app, false);
                 }
                 "restart" => {
-                    // アプリを再起動
-                    app.restart();
+                    // 書き込みを終えてからアプリを再起動
+                    shutdown::exit(app, true);
+                }
+                "watcher_pause" => send_watcher_command(app, WatcherCommand::Pause),
+                "watcher_resume" => send_watcher_command(app, WatcherCommand::Resume),
+                "watcher_rescan" => send_watcher_command(app, WatcherCommand::Rescan),
+                "watcher_switch" => {
+                    // 追跡するログファイルを選ぶ
+                    let handle = app.clone();
+                    app.dialog()
+                        .file()
+                        .add_filter("VRChat Log", &["txt"])
+                        .pick_file(move |file| {
+                            if let Some(path) = file.and_then(|f| f.into_path().ok()) {
+                                send_watcher_command(&handle, WatcherCommand::SwitchTo(Some(path)));
+                            }
+                        });
                 }
+                "watcher_follow" => send_watcher_command(app, WatcherCommand::SwitchTo(None)),
                 "show" => {
                     // ウィンドウを表示
                     if let Some(window) = app.get_webview_window("main") {
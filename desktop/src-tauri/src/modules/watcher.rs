@@ -1,32 +1,123 @@
-use crate::db::repositories::settings::WatcherState;
+use crate::db::repositories::custom_rules::CustomRule;
+use crate::db::repositories::watcher_states::WatcherState;
 use crate::db::DB;
-use crate::utils::date::{i64_to_str, str_to_i64}; // 💡 日付ユーティリティを追加
+use crate::modules::archiver;
+use crate::modules::process::{self, ProcessAccounts, ProcessChange, ProcessUpdate, VrcProcess};
+use crate::modules::screenshots;
+use crate::utils::date::{
+    i64_to_str, local_to_i64, naive_to_i64, parse_log_time, resolve_local_offset,
+}; // 💡 日付ユーティリティを追加
+use crate::utils::hash::{event_hash_v3, with_sequence};
+use crate::utils::instance::InstanceInfo;
+use crate::utils::logdir;
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use regex::{Captures, Regex};
+use sea_orm::DbErr;
 use serde::{Deserialize, Serialize};
 use specta::Type;
-use std::collections::hash_map::DefaultHasher;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, File};
-use std::hash::{Hash, Hasher};
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use tauri::async_runtime::JoinHandle;
 use tauri::AppHandle;
 use tauri_specta::Event;
+use tokio_util::sync::CancellationToken;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
 pub struct WatcherStatus {
+    #[serde(rename = "isAppRunning")]
     pub is_app_running: bool,
+    #[serde(rename = "lastSeenTimestamp")]
     pub last_seen_timestamp: i64, // 💡 String -> i64
+    /// 最後に行を読んだファイル
+    #[serde(rename = "currentFile")]
+    pub current_file: Option<String>,
+    /// currentFile の読み込み位置とファイルサイズ (position < fileLength なら未読がある)
+    pub position: u64,
+    #[serde(rename = "fileLength")]
+    pub file_length: u64,
+    #[serde(rename = "watchedFiles")]
+    pub watched_files: u32,
+    /// 起動してから読んだ行数 (追跡を終えたファイルの分も含む)
+    #[serde(rename = "linesRead")]
+    pub lines_read: u64,
+    #[serde(rename = "matchedLines")]
+    pub matched_lines: u64,
+    #[serde(rename = "unmatchedLines")]
+    pub unmatched_lines: u64,
+    /// UTF-8 として不正なバイトを含んでいた行の数 (置換文字で読み進めている)
+    #[serde(rename = "malformedLines")]
+    pub malformed_lines: u64,
+    /// 未読がある場合、最後に読んだ行のログ時刻からの経過秒数 (読み切っていれば 0)
+    #[serde(rename = "lagSeconds")]
+    pub lag_seconds: i64,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    /// Pause で読み込みを止めている
+    pub paused: bool,
+    /// SwitchTo で固定しているファイル (None なら最新のログを自動で追跡)
+    #[serde(rename = "pinnedFile")]
+    pub pinned_file: Option<String>,
+}
+
+impl WatcherStatus {
+    /// ファイルサイズと遅延を今の値で取り直す (watcher が止まっていても分かるように)
+    fn refresh_lag(&mut self) {
+        if let Some(len) = self
+            .current_file
+            .as_ref()
+            .and_then(|f| Path::new(f).metadata().ok())
+            .map(|m| m.len())
+        {
+            self.file_length = len;
+        }
+        self.lag_seconds = if self.position < self.file_length && self.last_seen_timestamp > 0 {
+            ((chrono::Utc::now().timestamp_millis() - self.last_seen_timestamp) / 1000).max(0)
+        } else {
+            0
+        };
+    }
+}
+
+/// WatcherStatus が変わったときに送るイベント
+#[derive(Clone, Serialize, Deserialize, Type, Event)]
+pub struct WatcherStatusChanged(pub WatcherStatus);
+
+/// watcher への操作 (パーサーの変更を特定のログで確かめる時などに使う)
+#[derive(Clone, Debug)]
+pub enum WatcherCommand {
+    /// 読み込みを止める (位置は保存される)
+    Pause,
+    Resume,
+    /// 現在のファイルを先頭から読み直す (取り込み済みのイベントは hash で除外される)
+    Rescan,
+    /// 指定したファイルだけを先頭から追跡する (None で最新のログの自動追跡に戻る)
+    SwitchTo(Option<PathBuf>),
 }
 
 pub struct WatcherService {
-    pub handle: JoinHandle<()>,
+    pub handle: Mutex<Option<JoinHandle<()>>>,
     pub status: Arc<RwLock<WatcherStatus>>,
+    commands: tokio::sync::mpsc::UnboundedSender<WatcherCommand>,
 }
 impl WatcherService {
+    /// 終了時に、書き込みの完了を待つためのハンドルを取り出す
+    pub fn take_handle(&self) -> Option<JoinHandle<()>> {
+        self.handle.lock().ok()?.take()
+    }
+
+    pub fn send(&self, command: WatcherCommand) -> Result<(), String> {
+        self.commands
+            .send(command)
+            .map_err(|_| "Log watcher is not running".to_string())
+    }
+
     pub fn is_app_running(&self) -> bool {
         self.status
             .read()
@@ -41,6 +132,12 @@ impl WatcherService {
             .map(|s| s.last_seen_timestamp)
             .unwrap_or(0)
     }
+
+    pub fn status(&self) -> WatcherStatus {
+        let mut status = self.status.read().map(|s| s.clone()).unwrap_or_default();
+        status.refresh_lag();
+        status
+    }
 }
 
 // ================================================================
@@ -73,6 +170,31 @@ pub enum VrcLogEvent {
         user_id: String,
     },
     SelfLeft,
+    VideoPlay {
+        url: String,
+        requested_by: Option<String>,
+    },
+    AvatarChange {
+        player_name: String,
+        avatar_name: String,
+    },
+    Screenshot {
+        path: String,
+    },
+    Custom {
+        rule: String,
+        fields: BTreeMap<String, String>,
+    },
+}
+
+impl VrcLogEvent {
+    /// InstanceJoin の instance_id を分解した情報 (それ以外のイベントは None)
+    pub fn instance_info(&self) -> Option<InstanceInfo> {
+        match self {
+            VrcLogEvent::InstanceJoin { instance_id, .. } => InstanceInfo::parse(instance_id),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Type, Event)]
@@ -80,6 +202,13 @@ pub struct LogPayload {
     pub event: VrcLogEvent,
     pub timestamp: i64, // 💡 String -> i64
     pub hash: i64,
+    /// InstanceJoin の場合のみ、instance_id を分解した情報 (hash には含めない)
+    #[serde(default)]
+    pub instance: Option<InstanceInfo>,
+    /// ログを書いたクライアントのアカウント (Login イベントの user_id)。
+    /// 別アカウントのクライアントが同じ秒に書いた同一イベントを区別するため、hash にも含める
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 struct LogDefinition {
@@ -87,6 +216,9 @@ struct LogDefinition {
     factory: fn(&Captures) -> VrcLogEvent,
 }
 
+/// LOG_DEFINITIONS を変更したら上げる (保存済みのログを再解析する目安)
+pub const PARSER_VERSION: u32 = 3;
+
 const LOG_DEFINITIONS: &[LogDefinition] = &[
     LogDefinition {
         pattern_part: r"VRCNP: Server started",
@@ -134,73 +266,329 @@ const LOG_DEFINITIONS: &[LogDefinition] = &[
         pattern_part: r"\[Behaviour\] OnLeftRoom",
         factory: |_| VrcLogEvent::SelfLeft,
     },
+    LogDefinition {
+        pattern_part: r"\[Behaviour\] Switching (.+?) to avatar (.+)",
+        factory: |caps| VrcLogEvent::AvatarChange {
+            player_name: caps[2].to_string(),
+            avatar_name: caps[3].to_string(),
+        },
+    },
+    LogDefinition {
+        pattern_part: r"\[VRC Camera\] Took screenshot to: (.+)",
+        factory: |caps| VrcLogEvent::Screenshot {
+            path: caps[2].trim_end().to_string(),
+        },
+    },
+    // 動画プレイヤー: USharpVideo はリクエストしたユーザー名も出力する。
+    // タグは "[<color=#9C6994>USharpVideo</color>]" のように色付きで出力される
+    LogDefinition {
+        pattern_part: r"(?:<color=[^>]*>)?\[(?:<color=[^>]*>)?USharpVideo(?:</color>)?\](?:</color>)? Started video load for URL: (.+), requested by (.+)",
+        factory: |caps| VrcLogEvent::VideoPlay {
+            url: caps[2].to_string(),
+            requested_by: Some(caps[3].to_string()),
+        },
+    },
+    LogDefinition {
+        pattern_part: r"(?:<color=[^>]*>)?\[(?:<color=[^>]*>)?USharpVideo(?:</color>)?\](?:</color>)? Started video load for URL: (.+)",
+        factory: |caps| VrcLogEvent::VideoPlay {
+            url: caps[2].to_string(),
+            requested_by: None,
+        },
+    },
+    LogDefinition {
+        pattern_part: r"\[Video Playback\] Attempting to resolve URL '(.+)'",
+        factory: |caps| VrcLogEvent::VideoPlay {
+            url: caps[2].to_string(),
+            requested_by: None,
+        },
+    },
+    LogDefinition {
+        pattern_part: r"\[AVProVideo\] Opening (\S+)",
+        factory: |caps| VrcLogEvent::VideoPlay {
+            url: caps[2].to_string(),
+            requested_by: None,
+        },
+    },
 ];
 
+const TS_PREFIX: &str = r"^(\d{4}\.\d{2}\.\d{2} \d{2}:\d{2}:\d{2}).*";
+
+enum MatcherKind {
+    Builtin(fn(&Captures) -> VrcLogEvent),
+    /// custom_rules テーブル由来のルール (名前付きキャプチャを fields に詰める)
+    Custom {
+        rule: String,
+    },
+}
+
 struct CompiledMatcher {
     regex: Regex,
-    factory: fn(&Captures) -> VrcLogEvent,
+    kind: MatcherKind,
 }
 
-fn get_compiled_matchers() -> &'static Vec<CompiledMatcher> {
-    static CACHE: OnceLock<Vec<CompiledMatcher>> = OnceLock::new();
-    CACHE.get_or_init(|| {
-        let ts_prefix = r"^(\d{4}\.\d{2}\.\d{2} \d{2}:\d{2}:\d{2}).*";
-        LOG_DEFINITIONS
-            .iter()
-            .map(|def| {
-                let full_pattern = format!("{}{}", ts_prefix, def.pattern_part);
-                CompiledMatcher {
-                    regex: Regex::new(&full_pattern).expect("Regex compile failed"),
-                    factory: def.factory,
-                }
-            })
-            .collect()
+impl CompiledMatcher {
+    fn build_event(&self, caps: &Captures) -> VrcLogEvent {
+        match &self.kind {
+            MatcherKind::Builtin(factory) => factory(caps),
+            MatcherKind::Custom { rule } => VrcLogEvent::Custom {
+                rule: rule.clone(),
+                fields: capture_fields(&self.regex, caps),
+            },
+        }
+    }
+}
+
+fn capture_fields(regex: &Regex, caps: &Captures) -> BTreeMap<String, String> {
+    regex
+        .capture_names()
+        .flatten()
+        .filter_map(|name| {
+            caps.name(name)
+                .map(|m| (name.to_string(), m.as_str().to_string()))
+        })
+        .collect()
+}
+
+// 組み込みルール + カスタムルール。カスタムルールの保存時に丸ごと差し替える
+static MATCHERS: RwLock<Option<Arc<Vec<CompiledMatcher>>>> = RwLock::new(None);
+
+fn build_matchers(custom: Vec<CompiledMatcher>) -> Vec<CompiledMatcher> {
+    LOG_DEFINITIONS
+        .iter()
+        .map(|def| {
+            let full_pattern = format!("{}{}", TS_PREFIX, def.pattern_part);
+            CompiledMatcher {
+                regex: Regex::new(&full_pattern).expect("Regex compile failed"),
+                kind: MatcherKind::Builtin(def.factory),
+            }
+        })
+        .chain(custom)
+        .collect()
+}
+
+fn get_compiled_matchers() -> Arc<Vec<CompiledMatcher>> {
+    if let Some(matchers) = MATCHERS.read().ok().and_then(|m| m.clone()) {
+        return matchers;
+    }
+    let mut guard = MATCHERS.write().unwrap_or_else(|e| e.into_inner());
+    guard
+        .get_or_insert_with(|| Arc::new(build_matchers(Vec::new())))
+        .clone()
+}
+
+/// カスタムルールのパターンをタイムスタンプ部分付きでコンパイルする
+pub fn compile_custom_rule(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("{}{}", TS_PREFIX, pattern)).map_err(|e| e.to_string())
+}
+
+/// カスタムルールを1行に当てた結果
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct CustomRuleTest {
+    /// パターンがマッチした場合の名前付きキャプチャ
+    pub fields: Option<BTreeMap<String, String>>,
+    /// 先に試されるルール (組み込みルール、または先に保存されたカスタムルール) がマッチするため、
+    /// 取り込み時にはこのルールにならない場合の、そのルール (組み込みはイベントの種類、カスタムはルール名)
+    #[serde(rename = "shadowedBy")]
+    pub shadowed_by: Option<String>,
+}
+
+/// パターンを1行に当てて、マッチした場合は名前付きキャプチャを返す。
+/// 取り込み時と同じ順にマッチャーを試し、先にマッチして取り込まれるルールがあればそれも返す
+pub fn test_custom_rule(pattern: &str, line: &str) -> Result<CustomRuleTest, String> {
+    let regex = compile_custom_rule(pattern)?;
+    let line = line.trim();
+    let fields = regex
+        .captures(line)
+        .map(|caps| capture_fields(&regex, &caps));
+
+    let shadowed_by = fields
+        .as_ref()
+        .and_then(|_| shadowing_rule(&get_compiled_matchers(), &regex, line));
+
+    Ok(CustomRuleTest {
+        fields,
+        shadowed_by,
     })
 }
 
-// 💡 引数を i64 に変更
-fn gen_hash(timestamp: i64, event: &VrcLogEvent) -> i64 {
-    let mut hasher = DefaultHasher::new();
-    timestamp.hash(&mut hasher);
-    event.hash(&mut hasher);
-    hasher.finish() as i64
+/// regex より先に試されて line にマッチするルール。
+/// 保存済みの同じパターンまでに、マッチするものがあればそちらが勝つ
+fn shadowing_rule(matchers: &[CompiledMatcher], regex: &Regex, line: &str) -> Option<String> {
+    matchers
+        .iter()
+        .take_while(|m| m.regex.as_str() != regex.as_str())
+        .find_map(|m| {
+            let caps = m.regex.captures(line)?;
+            Some(match m.build_event(&caps) {
+                VrcLogEvent::Custom { rule, .. } => rule,
+                event => event_type_name(&event),
+            })
+        })
+}
+
+/// logs.data の "type" と同じイベントの種類名 ("PlayerJoin" など)
+fn event_type_name(event: &VrcLogEvent) -> String {
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|v| v.get("type")?.as_str().map(str::to_owned))
+        .unwrap_or_default()
 }
 
-// 💡 戻り値を Option<i64> に変更
-pub fn extract_timestamp(line: &str) -> Option<i64> {
+/// カスタムルールを差し替える (コンパイルできないルールはスキップ)
+pub fn set_custom_rules(rules: &[CustomRule]) {
+    let custom = rules
+        .iter()
+        .filter_map(|rule| match compile_custom_rule(&rule.pattern) {
+            Ok(regex) => Some(CompiledMatcher {
+                regex,
+                kind: MatcherKind::Custom {
+                    rule: rule.name.clone(),
+                },
+            }),
+            Err(e) => {
+                eprintln!("Skipping invalid custom rule '{}': {}", rule.name, e);
+                None
+            }
+        })
+        .collect();
+
+    let matchers = Arc::new(build_matchers(custom));
+    *MATCHERS.write().unwrap_or_else(|e| e.into_inner()) = Some(matchers);
+}
+
+/// DB からカスタムルールを読み込み直してマッチャーに反映する
+pub async fn reload_custom_rules(db: &DB) -> Result<(), DbErr> {
+    let rules = db.custom_rules().get_rules().await?;
+    set_custom_rules(&rules);
+    Ok(())
+}
+
+/// イベントの JSON (logs.data と同じ形式) とアカウントから安定したハッシュを計算する。
+/// アカウントを含めないと、同じインスタンスにいる別アカウントのクライアントの同じ行が重複として捨てられる
+fn gen_hash(timestamp: i64, event: &VrcLogEvent, account: Option<&str>) -> i64 {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    event_hash_v3(timestamp, &data, account)
+}
+
+/// VRChat のログは秒単位なので、同じ秒の同一イベント (再Join等) はハッシュが衝突する。
+/// 1ファイルを先頭から順に読む間、秒ごとの出現回数でハッシュを派生させて区別する。
+/// 同じファイルを読み直せば同じ番号が振られるため、再インポートは冪等のまま。
+/// 途中から再開する場合も同じ番号になるよう、WatcherState に保存して引き継ぐ
+#[derive(Default, Serialize, Deserialize)]
+pub struct EventSequencer {
+    timestamp: i64,
+    counts: HashMap<i64, u32>,
+}
+
+impl EventSequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn assign(&mut self, payload: &mut LogPayload) {
+        if payload.timestamp != self.timestamp {
+            self.timestamp = payload.timestamp;
+            self.counts.clear();
+        }
+        let seq = self.counts.entry(payload.hash).or_insert(0);
+        payload.hash = with_sequence(payload.hash, *seq);
+        *seq += 1;
+    }
+}
+
+/// ファイルの各行を変換した UTC オフセットの記録。
+/// この PC のローカル時間として読んだログも、再解析やバックフィルで同じ時刻 (= 同じハッシュ) に
+/// なるよう、実際に使ったオフセットを行のバイト位置の範囲ごとに保存しておく
+/// (旅行やタイムゾーンの変更の後に読み直しても、記録済みの行はずれない)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UtcOffsetRanges {
+    /// (範囲の先頭の行のバイト位置, オフセット秒)。次の範囲の先頭まで同じオフセットを使う
+    ranges: Vec<(u64, i32)>,
+    /// 記録した最後の行の終わり。ここから先の行はまだオフセットが決まっていない
+    until: u64,
+}
+
+impl UtcOffsetRanges {
+    /// DB に保存した JSON から復元する (無い・壊れている場合は空)
+    pub fn from_json(json: Option<&str>) -> Self {
+        json.and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn to_json(&self) -> Option<String> {
+        if self.ranges.is_empty() {
+            return None;
+        }
+        serde_json::to_string(self).ok()
+    }
+
+    /// position から始まる行を変換したオフセット (まだ読んでいない行は None)
+    pub fn get(&self, position: u64) -> Option<FixedOffset> {
+        if position >= self.until {
+            return None;
+        }
+        self.ranges
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= position)
+            .and_then(|(_, secs)| FixedOffset::east_opt(*secs))
+    }
+
+    /// position から end までの行を offset で変換したことを記録する
+    pub fn record(&mut self, position: u64, end: u64, offset: FixedOffset) {
+        if position < self.until {
+            return;
+        }
+        let secs = offset.local_minus_utc();
+        if self.ranges.last().is_none_or(|(_, last)| *last != secs) {
+            self.ranges.push((position, secs));
+        }
+        self.until = end;
+    }
+}
+
+/// 行頭の時刻 (タイムゾーン無しのローカル日時)
+pub fn extract_log_time(line: &str) -> Option<NaiveDateTime> {
     static RE: OnceLock<Regex> = OnceLock::new();
     let re = RE.get_or_init(|| Regex::new(r"^(\d{4}\.\d{2}\.\d{2} \d{2}:\d{2}:\d{2})").unwrap());
 
-    if let Some(caps) = re.captures(line) {
-        return Some(str_to_i64(&caps[1])); // 💡 即時 i64 化
-    }
-    None
+    re.captures(line).and_then(|caps| parse_log_time(&caps[1]))
 }
 
-/// 1行を解析してPayloadを返す
+/// 生バイトの1行を文字列にする。不正な UTF-8 は U+FFFD に置換し、置換が発生したかを返す
+pub fn decode_log_line(bytes: &[u8]) -> (Cow<'_, str>, bool) {
+    encoding_rs::UTF_8.decode_without_bom_handling(bytes)
+}
+
+/// 1行を解析してPayloadを返す (時刻はこの PC のローカル時間として解釈)
 pub fn parse_log_line(line: &str) -> Option<LogPayload> {
+    parse_log_line_in(line, None)
+}
+
+/// 時刻を指定した UTC オフセットで変換する版 (None はこの PC のローカル時間)
+pub fn parse_log_line_in(line: &str, utc_offset: Option<FixedOffset>) -> Option<LogPayload> {
     let line = line.trim();
     if line.is_empty() {
         return None;
     }
 
-    for matcher in get_compiled_matchers() {
+    for matcher in get_compiled_matchers().iter() {
         if let Some(caps) = matcher.regex.captures(line) {
-            let event = (matcher.factory)(&caps);
+            let event = matcher.build_event(&caps);
 
             // 💡 文字列として抽出してから即座に i64 に変換
-            let ts_str = caps.get(1).map_or("unknown", |m| m.as_str());
-            let timestamp = str_to_i64(ts_str);
-            if timestamp == 0 {
-                return None;
-            }
+            let ndt = caps.get(1).and_then(|m| parse_log_time(m.as_str()))?;
+            let offset = utc_offset.unwrap_or_else(|| resolve_local_offset(&ndt));
+            let timestamp = naive_to_i64(&ndt, offset);
 
-            let hash = gen_hash(timestamp, &event); // 💡 参照渡し(&)を解除
+            let hash = gen_hash(timestamp, &event, None); // 💡 参照渡し(&)を解除
 
             return Some(LogPayload {
+                instance: event.instance_info(),
                 event,
                 timestamp,
                 hash,
+                account: None,
             });
         }
     }
@@ -211,264 +599,1763 @@ pub fn parse_log_line(line: &str) -> Option<LogPayload> {
 // Section B: File Watcher Logic
 // ================================================================
 
-fn get_vrc_log_dir() -> Option<PathBuf> {
-    dirs::data_local_dir().map(|path| {
-        path.join("..")
-            .join("LocalLow")
-            .join("VRChat")
-            .join("VRChat")
-    })
+/// ログディレクトリを解決する (設定 > 自動検出 > Windows標準パス の順)
+pub async fn get_vrc_log_dir(db: &DB) -> Option<PathBuf> {
+    if let Ok(Some(dir)) = db.settings().get_log_dir().await {
+        return Some(PathBuf::from(dir));
+    }
+    logdir::find_log_dir_candidates()
+        .into_iter()
+        .next()
+        .or_else(logdir::default_log_dir)
 }
 
-fn get_latest_log_path() -> Option<PathBuf> {
-    let log_dir = get_vrc_log_dir()?;
-    let entries = fs::read_dir(log_dir).ok()?;
+fn is_log_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| name.starts_with("output_log") && name.ends_with(".txt"))
+}
 
-    let mut logs: Vec<PathBuf> = entries
+pub(crate) fn list_log_files(log_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(log_dir) else {
+        return Vec::new();
+    };
+    entries
         .filter_map(|entry| entry.ok())
         .map(|entry| entry.path())
-        .filter(|path| {
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                name.starts_with("output_log") && name.ends_with(".txt")
-            } else {
-                false
-            }
-        })
-        .collect();
+        .filter(|path| is_log_file(path))
+        .collect()
+}
+
+/// output_log_YYYY-MM-DD_HH-MM-SS.txt のファイル名に埋め込まれた作成時刻 (ローカル時間のまま比較用)
+pub(crate) fn log_file_name_time(path: &Path) -> Option<NaiveDateTime> {
+    let name = path.file_stem()?.to_str()?;
+    let ts = name.strip_prefix("output_log_")?;
+    NaiveDateTime::parse_from_str(ts, "%Y-%m-%d_%H-%M-%S").ok()
+}
+
+/// ログファイルの並び順のキー。
+/// 作成日時 (created) は Linux の多くのファイルシステムで取得できないため、
+/// ファイル名の時刻を優先し、無ければ更新日時 (mtime) をローカル時間にして使う
+fn log_sort_time(path: &Path) -> Option<NaiveDateTime> {
+    log_file_name_time(path).or_else(|| {
+        let mtime = path.metadata().and_then(|m| m.modified()).ok()?;
+        Some(DateTime::<Local>::from(mtime).naive_local())
+    })
+}
 
-    logs.sort_by_key(|path| path.metadata().and_then(|m| m.created()).ok());
+/// 古い順に並べる (同時刻はファイル名順)
+pub(crate) fn sort_log_files(logs: &mut [PathBuf]) {
+    logs.sort_by_cached_key(|path| (log_sort_time(path), path.file_name().map(|n| n.to_owned())));
+}
+
+fn get_latest_log_path(log_dir: &Path) -> Option<PathBuf> {
+    let mut logs = list_log_files(log_dir);
+    sort_log_files(&mut logs);
     logs.last().cloned()
 }
 
 // 💡 引数を i64 に変更
 pub fn create_invalid_app_stop_payload(last_timestamp: i64) -> LogPayload {
     let event = VrcLogEvent::InvalidAppStop;
-    let hash = gen_hash(last_timestamp, &event);
+    let hash = gen_hash(last_timestamp, &event, None);
     LogPayload {
         event,
         timestamp: last_timestamp,
         hash,
+        instance: None,
+        account: None,
     }
 }
 
-async fn watch_loop(app: AppHandle, db: DB, shared_status: Arc<RwLock<WatcherStatus>>) {
-    let mut rotation_check_interval = tokio::time::interval(Duration::from_secs(5));
-    let mut current_log_path = get_latest_log_path();
+/// 変更通知を使えない間 (watcher を作れない・エラーが届いた) の読み直し間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// 変更通知を使える間も、取りこぼし対策としてこの間隔で読み直す
+/// (Windows では VRChat がログを開いたままの間、追記の通知が来ないことがあるため)
+const SAFETY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// 最新でなくても、この期間内に更新されたファイルは別クライアントのログとして追跡する
+pub(crate) const ACTIVE_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// AppStop 済み (または起動前) のファイルは、この期間更新が無ければ追跡をやめる
+const CLOSE_GRACE: Duration = Duration::from_secs(30);
+/// 起動中のまま更新が止まったファイルはクラッシュとみなす
+const CRASH_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// ログのファイル名の時刻とプロセスの開始時刻がこれ以上離れていれば、別のクライアントとみなす
+const PROCESS_LOG_MATCH_WINDOW_MS: i64 = 5 * 60 * 1000;
+/// 1ファイルを続けて読む最大行数 (他のファイルを待たせないため)
+const LINES_PER_TURN: usize = 256;
+/// DB への書き込みはこの件数・時間ごとに1トランザクションにまとめる
+const BATCH_SIZE: usize = 200;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
 
-    let current_path_str = current_log_path
-        .as_ref()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FsNotification {
+    /// 既存ログへの追記など
+    Modified,
+    /// 新しい output_log が作られた (ローテーション / 別クライアントの起動)
+    Created,
+    /// 通知を取りこぼした可能性がある (バッファのあふれなど)
+    Failed,
+}
 
-    // 💡 i64 で初期化
-    let mut last_seen_timestamp: i64 = 0;
-    let mut is_app_running = false;
-    let mut current_position: u64 = 0;
+/// ログディレクトリの変更通知 (Linux では inotify) を購読する
+fn create_fs_watcher(
+    dir: &Path,
+    tx: tokio::sync::mpsc::UnboundedSender<FsNotification>,
+) -> Option<RecommendedWatcher> {
+    let handler = move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("File notification error, polling until it recovers: {}", e);
+                let _ = tx.send(FsNotification::Failed);
+                return;
+            }
+        };
+        let is_log = event.paths.iter().any(|p| is_log_file(p));
+        if !is_log {
+            return;
+        }
+        let notification = match event.kind {
+            EventKind::Create(_) => FsNotification::Created,
+            EventKind::Modify(_) => FsNotification::Modified,
+            _ => return,
+        };
+        let _ = tx.send(notification);
+    };
 
-    if let Ok(Some(saved_state)) = db.settings().get_watcher_state().await {
-        if saved_state.log_path == current_path_str && !current_path_str.is_empty() {
+    let mut watcher = match notify::recommended_watcher(handler) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!(
+                "File notifications unavailable, falling back to polling: {}",
+                e
+            );
+            return None;
+        }
+    };
+    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+        eprintln!("Failed to watch {:?}, falling back to polling: {}", dir, e);
+        return None;
+    }
+    println!("Watching log directory for changes: {:?}", dir);
+    Some(watcher)
+}
+
+/// 最終更新からの経過時間 (取得できない場合は十分古いものとして扱う)
+pub(crate) fn idle_duration(path: &Path) -> Duration {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .unwrap_or(Duration::MAX)
+}
+
+pub(crate) enum TailRead {
+    /// 1行読んでイベントになった
+    Payload(LogPayload),
+    /// 1行読んだが対象外の行だった
+    Skipped,
+    /// 読める完結した行が無い
+    Eof,
+}
+
+/// 1つのログファイル (= 1つの VRChat クライアント) の追跡状態
+pub(crate) struct LogTail {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    /// 確定した行境界のバイト位置 (WatcherState に保存する値)
+    position: u64,
+    /// 改行待ちの読みかけの行
+    line_buf: Vec<u8>,
+    sequencer: EventSequencer,
+    is_app_running: bool,
+    last_seen_timestamp: i64,
+    /// このファイルを書いているアカウント (Login イベントの user_id)
+    account: Option<String>,
+    /// 別の PC で書かれたログとして指定された UTC オフセット。
+    /// None ならこの PC のローカル時間として、夏時間も考慮して1行ずつ変換する
+    utc_offset: Option<FixedOffset>,
+    /// 各行を変換したオフセット。記録済みの行は読み直しても同じオフセットで変換する
+    utc_offsets: UtcOffsetRanges,
+    /// このファイルを書いていたプロセスが終了した時刻 (読み切った時点で起動中なら落ちている)
+    exited_at: Option<i64>,
+    pub(crate) malformed_lines: u64,
+    lines_read: u64,
+    matched_lines: u64,
+    last_error: Option<String>,
+    at_eof: bool,
+    /// DB に保存した状態から進んでいる
+    dirty: bool,
+}
+
+impl LogTail {
+    pub(crate) fn open(path: PathBuf, saved: Option<&WatcherState>) -> Self {
+        let mut tail = Self {
+            path,
+            reader: None,
+            position: saved.map_or(0, |s| s.last_position),
+            line_buf: Vec::new(),
+            sequencer: saved
+                .and_then(|s| s.sequencer.as_deref())
+                .and_then(|json| serde_json::from_str(json).ok())
+                .unwrap_or_default(),
+            is_app_running: saved.is_some_and(|s| s.is_running),
+            last_seen_timestamp: saved.map_or(0, |s| s.last_timestamp),
+            account: saved.and_then(|s| s.account.clone()),
+            utc_offset: saved
+                .and_then(|s| s.utc_offset)
+                .and_then(FixedOffset::east_opt),
+            utc_offsets: UtcOffsetRanges::from_json(saved.and_then(|s| s.utc_offsets.as_deref())),
+            exited_at: None,
+            malformed_lines: 0,
+            lines_read: 0,
+            matched_lines: 0,
+            last_error: None,
+            at_eof: false,
+            dirty: false,
+        };
+        tail.reader = tail.open_reader();
+        tail
+    }
+
+    /// 別の PC・タイムゾーンで書かれたログ用に、オフセットを指定する
+    pub(crate) fn with_utc_offset(mut self, utc_offset: Option<FixedOffset>) -> Self {
+        if utc_offset.is_some() {
+            self.utc_offset = utc_offset;
+        }
+        self
+    }
+
+    /// 以前に読んだ時に記録したオフセットを引き継ぐ (再解析・バックフィル用)
+    pub(crate) fn with_utc_offsets(mut self, utc_offsets: UtcOffsetRanges) -> Self {
+        self.utc_offsets = utc_offsets;
+        self
+    }
+
+    /// 指定された UTC オフセット (秒)。再解析でも同じ時刻にするため保存しておく
+    pub(crate) fn utc_offset_secs(&self) -> Option<i32> {
+        self.utc_offset.map(|o| o.local_minus_utc())
+    }
+
+    /// 実際に各行を変換したオフセット。再解析でも同じ時刻にするため保存しておく
+    pub(crate) fn utc_offsets(&self) -> &UtcOffsetRanges {
+        &self.utc_offsets
+    }
+
+    fn open_reader(&mut self) -> Option<BufReader<File>> {
+        let mut f = File::open(&self.path).ok()?;
+        let file_len = f.metadata().map(|m| m.len()).unwrap_or(0);
+        if self.position > file_len {
             println!(
-                "Resuming watcher from position: {}",
-                saved_state.last_position
+                "Saved position {} > File length {}. Resetting to 0.",
+                self.position, file_len
             );
-            current_position = saved_state.last_position;
-            is_app_running = saved_state.is_running;
-            last_seen_timestamp = str_to_i64(&saved_state.last_timestamp); // 💡 DBのStringをi64に戻す
-        } else if !saved_state.log_path.is_empty() {
+            self.position = 0;
+        }
+        if let Err(e) = f.seek(SeekFrom::Start(self.position)) {
+            eprintln!("Seek failed: {}, resetting to 0", e);
+            let _ = f.seek(SeekFrom::Start(0));
+            self.position = 0;
+        }
+        Some(BufReader::new(f))
+    }
+
+    pub(crate) fn read(&mut self) -> TailRead {
+        let Some(r) = &mut self.reader else {
+            return TailRead::Eof;
+        };
+
+        // read_line は不正な UTF-8 でエラーになり同じ位置で止まり続けるため、
+        // 生バイトで読んでから置換デコードし、必ず位置を進める
+        match r.read_until(b'\n', &mut self.line_buf) {
+            Ok(0) if self.was_truncated() => {
+                // 読んだ位置より短くなった (切り詰めて書き直された) ファイルは先頭から読み直す
+                self.restart();
+                self.read()
+            }
+            Ok(0) => {
+                self.at_eof = true;
+                TailRead::Eof
+            }
+            Ok(_) if !self.line_buf.ends_with(b"\n") => {
+                // VRChat が行の途中までしか書き出していない。
+                // 読んだ分は line_buf に残し、改行が来てから1行として処理する
+                // (position は確定した行境界のままにしておく)
+                self.at_eof = true;
+                TailRead::Eof
+            }
+            Ok(_) => {
+                self.at_eof = false;
+                self.dirty = true;
+                self.position += self.line_buf.len() as u64;
+                match self.take_line() {
+                    Some(payload) => TailRead::Payload(payload),
+                    None => TailRead::Skipped,
+                }
+            }
+            Err(e) => {
+                eprintln!("Error reading log {:?}: {}", self.path, e);
+                self.last_error = Some(format!("Failed to read {:?}: {}", self.path, e));
+                self.at_eof = true;
+                TailRead::Eof
+            }
+        }
+    }
+
+    fn was_truncated(&self) -> bool {
+        let read_to = self.position + self.line_buf.len() as u64;
+        fs::metadata(&self.path).is_ok_and(|m| m.len() < read_to)
+    }
+
+    fn restart(&mut self) {
+        println!(
+            "Log file {:?} was truncated. Reading from the start.",
+            self.path
+        );
+        self.position = 0;
+        self.line_buf.clear();
+        self.sequencer = EventSequencer::new();
+        self.utc_offsets = UtcOffsetRanges::default();
+        self.dirty = true;
+        self.reader = self.open_reader();
+    }
+
+    /// line_buf の内容を1行として解析し、バッファを空にする
+    fn take_line(&mut self) -> Option<LogPayload> {
+        let buf = std::mem::take(&mut self.line_buf);
+        let payload = self.process_line(&buf);
+        self.line_buf = buf;
+        self.line_buf.clear();
+        payload
+    }
+
+    fn process_line(&mut self, bytes: &[u8]) -> Option<LogPayload> {
+        let (line, had_errors) = decode_log_line(bytes);
+        self.lines_read += 1;
+        if had_errors {
+            self.malformed_lines += 1;
+        }
+        // 夏時間の切り替えを跨ぐセッションもあるため、オフセットは1行ごとに決める。
+        // 切り替えで重複する時刻は、前の行より前に戻らない方にする (先頭から読み直しても同じ値になる)。
+        // 一度読んだ行は、その時に使ったオフセットで変換する (PC のタイムゾーンが変わってもずれない)
+        let end = self.position;
+        let start = end - bytes.len() as u64;
+        let timestamp = extract_log_time(&line).map(|ndt| {
+            let timestamp = match self.utc_offsets.get(start).or(self.utc_offset) {
+                Some(offset) => naive_to_i64(&ndt, offset),
+                None => local_to_i64(&ndt, self.last_seen_timestamp),
+            };
+            let offset_secs = (ndt.and_utc().timestamp_millis() - timestamp) / 1000;
+            if let Some(offset) = FixedOffset::east_opt(offset_secs as i32) {
+                self.utc_offsets.record(start, end, offset);
+            }
+            timestamp
+        });
+        if let Some(timestamp) = timestamp {
+            self.last_seen_timestamp = timestamp;
+        }
+
+        let mut payload = parse_log_line_in(&line, self.utc_offset)?;
+        if let Some(timestamp) = timestamp {
+            payload.timestamp = timestamp;
+        }
+        self.matched_lines += 1;
+        match &payload.event {
+            VrcLogEvent::AppStart => self.is_app_running = true,
+            VrcLogEvent::AppStop => self.is_app_running = false,
+            VrcLogEvent::Login { user_id, .. } => self.account = Some(user_id.clone()),
+            _ => {}
+        }
+        self.tag_account(&mut payload);
+        self.sequencer.assign(&mut payload);
+        Some(payload)
+    }
+
+    /// このファイルを書いているアカウントを付け、ハッシュにも含める
+    fn tag_account(&self, payload: &mut LogPayload) {
+        payload.account = self.account.clone();
+        payload.hash = gen_hash(
+            payload.timestamp,
+            &payload.event,
+            payload.account.as_deref(),
+        );
+    }
+
+    /// ファイル名の作成時刻 (UTC ミリ秒)。書いているプロセスを特定するのに使う
+    fn created_at(&self) -> Option<i64> {
+        let ndt = log_file_name_time(&self.path)?;
+        Some(naive_to_i64(&ndt, resolve_local_offset(&ndt)))
+    }
+
+    fn mark_exited(&mut self, stopped_at: i64) {
+        if self.is_app_running && self.exited_at.is_none() {
+            self.exited_at = Some(stopped_at);
+        }
+    }
+
+    /// プロセスが終了していて、読み切っても AppStop が無ければクラッシュとして
+    /// InvalidAppStop を返す (ファイルの更新が止まるのを待たない)
+    fn take_exit(&mut self) -> Option<LogPayload> {
+        if !self.at_eof || !self.is_app_running || self.exited_at.is_none() {
+            return None;
+        }
+        let payload = self.crash_payload();
+        println!(
+            "VRChat exited at {} without AppStop in {:?}. Inserting InvalidAppStop",
+            i64_to_str(payload.timestamp),
+            self.path
+        );
+        Some(payload)
+    }
+
+    /// 落ちたセッションを閉じる InvalidAppStop。時刻はプロセスの終了時刻 (分からなければ最後の行の時刻) にするが、
+    /// ハッシュは常に最後の行の時刻から作る。プロセスの情報が無い backfill・再解析 (finish) とも
+    /// 同じハッシュになり、同じファイルを取り込み直しても二重に記録されない
+    fn crash_payload(&mut self) -> LogPayload {
+        let mut payload = create_invalid_app_stop_payload(self.last_seen_timestamp);
+        self.tag_account(&mut payload);
+        if let Some(exited_at) = self.exited_at.take() {
+            payload.timestamp = exited_at.max(self.last_seen_timestamp);
+        }
+        self.is_app_running = false;
+        self.dirty = true;
+        payload
+    }
+
+    /// 読み切った上で書き込みが止まっていれば、追跡を終えてよい
+    fn is_finished(&self) -> bool {
+        if !self.at_eof {
+            return false;
+        }
+        let idle = idle_duration(&self.path);
+        if self.is_app_running {
+            idle > CRASH_TIMEOUT
+        } else {
+            idle > CLOSE_GRACE
+        }
+    }
+
+    /// 追跡を終える。旧ファイルへの書き込みは終わっているので改行待ちの末尾も確定させ、
+    /// 起動中のまま終わっていればクラッシュとして InvalidAppStop を返す
+    pub(crate) fn finish(&mut self) -> Vec<LogPayload> {
+        let mut payloads = Vec::new();
+        if !self.line_buf.is_empty() {
+            self.position += self.line_buf.len() as u64;
+            payloads.extend(self.take_line());
+        }
+        if self.is_app_running {
+            let crash_payload = self.crash_payload();
             println!(
-                "Rotation detected. Re-scanning old log fully: {}",
-                saved_state.log_path
+                "Crash detected in {:?}. Inserting InvalidAppStop at {}",
+                self.path,
+                i64_to_str(crash_payload.timestamp)
             );
+            payloads.push(crash_payload);
+        }
+        self.reader = None;
+        self.dirty = true;
+        payloads
+    }
 
-            let old_path = PathBuf::from(&saved_state.log_path);
-            if old_path.exists() {
-                if let Ok(file) = File::open(&old_path) {
-                    let reader = BufReader::new(file);
+    fn state(&self) -> WatcherState {
+        WatcherState {
+            log_path: self.path.to_string_lossy().to_string(),
+            is_running: self.is_app_running,
+            last_timestamp: self.last_seen_timestamp,
+            last_position: self.position,
+            account: self.account.clone(),
+            utc_offset: self.utc_offset_secs(),
+            utc_offsets: self.utc_offsets.to_json(),
+            sequencer: serde_json::to_string(&self.sequencer).ok(),
+        }
+    }
+}
 
-                    let mut temp_is_running = false;
-                    let mut temp_last_ts: i64 = 0; // 💡 i64
+/// 1行ごとにコミットすると大人数のインスタンスに入った時などに遅いため、
+/// DB への書き込みを溜めてまとめて行う
+struct WriteBuffer {
+    payloads: Vec<LogPayload>,
+    last_flush: Instant,
+}
 
-                    for l in reader.lines().map_while(Result::ok) {
-                        if let Some(ts) = extract_timestamp(&l) {
-                            temp_last_ts = ts;
-                        }
+impl WriteBuffer {
+    fn new() -> Self {
+        Self {
+            payloads: Vec::new(),
+            last_flush: Instant::now(),
+        }
+    }
 
-                        if let Some(payload) = parse_log_line(&l) {
-                            match payload.event {
-                                VrcLogEvent::AppStart => temp_is_running = true,
-                                VrcLogEvent::AppStop => temp_is_running = false,
-                                _ => {}
-                            }
-                            let _ = db.logs().insert_log(&payload).await;
-                        }
-                    }
+    fn is_due(&self) -> bool {
+        self.payloads.len() >= BATCH_SIZE || self.last_flush.elapsed() >= FLUSH_INTERVAL
+    }
+}
+
+/// 1回分の読み込みの結果
+#[derive(Default)]
+struct ReadPass {
+    payloads: Vec<LogPayload>,
+    read_any: bool,
+    /// 起動・終了・ログインなど、すぐに書き込むべきイベントを含む
+    force_flush: bool,
+    last_error: Option<String>,
+}
 
-                    if temp_is_running {
-                        println!(
-                            "Crash detected in old log. Inserting InvalidAppStop at {}",
-                            i64_to_str(temp_last_ts)
+/// 各ファイルを最大 LINES_PER_TURN 行ずつ読む。
+/// ファイル I/O は同期処理なので、HTTP サーバーやコマンドと同じランタイムを塞がないよう
+/// blocking スレッドで読み、解析結果だけを受け取る
+async fn read_pass(
+    mut tails: HashMap<PathBuf, LogTail>,
+) -> Result<(HashMap<PathBuf, LogTail>, ReadPass), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut pass = ReadPass::default();
+        for tail in tails.values_mut() {
+            for _ in 0..LINES_PER_TURN {
+                match tail.read() {
+                    TailRead::Eof => break,
+                    TailRead::Skipped => pass.read_any = true,
+                    TailRead::Payload(payload) => {
+                        pass.read_any = true;
+                        pass.force_flush |= matches!(
+                            payload.event,
+                            VrcLogEvent::AppStart
+                                | VrcLogEvent::AppStop
+                                | VrcLogEvent::Login { .. }
                         );
-                        let crash_payload = create_invalid_app_stop_payload(temp_last_ts);
-                        let _ = db.logs().insert_log(&crash_payload).await;
+                        pass.payloads.push(payload);
                     }
                 }
-            } else {
-                println!("Old log file not found. Skipping.");
             }
-
-            println!("Switching to new log file: {:?}", current_log_path);
-            current_position = 0;
-            is_app_running = false;
-            last_seen_timestamp = 0; // 💡 0にリセット
+            if let Some(payload) = tail.take_exit() {
+                pass.force_flush = true;
+                pass.payloads.push(payload);
+            }
+            if let Some(e) = tail.last_error.take() {
+                pass.last_error = Some(e);
+            }
         }
+        (tails, pass)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// watch_loop が読み取った結果の送り先。アプリでは AppHandle (フロントエンドへのイベント送信と
+/// アーカイブ) で、テストではログディレクトリへの書き込みから届くまでを計測するのに使う
+pub(crate) trait WatchSink: Send + Sync {
+    /// 読み取ったイベント
+    fn log(&self, payload: &LogPayload);
+    /// 追跡の状態の変化
+    fn status(&self, status: &WatcherStatus);
+    /// 追跡を終えたファイル (書き込みが終わっている)
+    fn closed(&self, db: &DB, path: PathBuf, utc_offset: Option<i32>, utc_offsets: UtcOffsetRanges);
+}
+
+impl WatchSink for AppHandle {
+    fn log(&self, payload: &LogPayload) {
+        let _ = payload.emit(self);
     }
 
-    let mut reader = match &current_log_path {
-        Some(path) => {
-            println!("Start watching log file: {:?}", path);
-            File::open(path).ok().map(|mut f| {
-                let file_len = f.metadata().map(|m| m.len()).unwrap_or(0);
-                if current_position > file_len {
-                    println!(
-                        "Saved position {} > File length {}. Resetting to 0.",
-                        current_position, file_len
-                    );
-                    current_position = 0;
-                }
-                if let Err(e) = f.seek(SeekFrom::Start(current_position)) {
-                    eprintln!("Seek failed: {}, resetting to 0", e);
-                    let _ = f.seek(SeekFrom::Start(0));
-                    current_position = 0;
-                }
-                BufReader::new(f)
-            })
+    fn status(&self, status: &WatcherStatus) {
+        let _ = WatcherStatusChanged(status.clone()).emit(self);
+    }
+
+    fn closed(
+        &self,
+        db: &DB,
+        path: PathBuf,
+        utc_offset: Option<i32>,
+        utc_offsets: UtcOffsetRanges,
+    ) {
+        // VRChat は古いログを自動で消すため、書き終わったファイルは手元に残しておく
+        archiver::spawn_archive(self.clone(), db.clone(), path, utc_offset, utc_offsets);
+    }
+}
+
+/// フロントエンドへはすぐに通知し、DB への書き込みはバッファに積む
+fn publish(sink: &dyn WatchSink, payload: LogPayload, buffer: &mut WriteBuffer) {
+    sink.log(&payload);
+    buffer.payloads.push(payload);
+}
+
+/// 溜まったログと、読み進めたファイルの状態を1トランザクションで書き込む。
+/// 失敗した場合はバッファと dirty を残し、次の flush で書き直す
+async fn flush<'a>(
+    db: &DB,
+    buffer: &mut WriteBuffer,
+    tails: impl IntoIterator<Item = &'a mut LogTail>,
+    diag: &mut Diagnostics,
+) {
+    let mut dirty: Vec<&mut LogTail> = tails.into_iter().filter(|t| t.dirty).collect();
+    buffer.last_flush = Instant::now();
+    if buffer.payloads.is_empty() && dirty.is_empty() {
+        return;
+    }
+
+    let states: Vec<WatcherState> = dirty.iter().map(|t| t.state()).collect();
+    match db.logs().insert_batch(&buffer.payloads, &states).await {
+        Ok(_) => {
+            buffer.payloads.clear();
+            for tail in dirty.iter_mut() {
+                tail.dirty = false;
+            }
         }
-        None => {
-            println!("No VRChat log file found yet.");
-            None
+        Err(e) => {
+            eprintln!("Failed to write logs: {}", e);
+            diag.last_error = Some(format!("Failed to write logs: {}", e));
         }
+    }
+}
+
+/// WatcherStatus 用の、追跡を終えたファイルの分も含めた集計
+#[derive(Default)]
+struct Diagnostics {
+    closed_lines_read: u64,
+    closed_matched_lines: u64,
+    closed_malformed_lines: u64,
+    last_error: Option<String>,
+    /// 起動している VRChat のプロセス数 (プロセスを監視できない環境では None)
+    vrc_processes: Option<usize>,
+    /// 最後に送った WatcherStatusChanged
+    last_emitted: Option<WatcherStatus>,
+    last_emit: Option<Instant>,
+}
+
+impl Diagnostics {
+    fn close(&mut self, tail: &LogTail) {
+        self.closed_lines_read += tail.lines_read;
+        self.closed_matched_lines += tail.matched_lines;
+        self.closed_malformed_lines += tail.malformed_lines;
+    }
+}
+
+/// 前回追跡していたファイルのうち、続きがあるもの・起動中のまま終わったものを再開する
+async fn resume_tails(db: &DB) -> HashMap<PathBuf, LogTail> {
+    let mut tails = HashMap::new();
+    let Ok(states) = db.watcher_states().get_states().await else {
+        return tails;
     };
 
-    let mut line = String::new();
-    let mut last_db_sync = Instant::now();
+    for state in states {
+        let path = PathBuf::from(&state.log_path);
+        let Ok(meta) = path.metadata() else {
+            // VRChat に削除されたログの状態は不要
+            let _ = db.watcher_states().delete_state(&state.log_path).await;
+            continue;
+        };
+        if state.is_running || meta.len() > state.last_position {
+            println!(
+                "Resuming watcher for {:?} from position: {}",
+                path, state.last_position
+            );
+            tails.insert(path.clone(), LogTail::open(path, Some(&state)));
+        }
+    }
+    tails
+}
 
-    loop {
-        let mut read_success = false;
-        let mut state_changed = false;
+/// ディレクトリを見直し、書き込まれているファイルを追跡に加え、止まったファイルを閉じる
+async fn refresh_tails(
+    sink: &dyn WatchSink,
+    db: &DB,
+    tails: &mut HashMap<PathBuf, LogTail>,
+    log_dir: Option<&Path>,
+    buffer: &mut WriteBuffer,
+    diag: &mut Diagnostics,
+) {
+    let latest = log_dir.and_then(get_latest_log_path);
 
-        if let Some(r) = &mut reader {
-            match r.read_line(&mut line) {
-                Ok(0) => { /* EOF */ }
-                Ok(bytes_read) => {
-                    current_position += bytes_read as u64;
+    if let Some(dir) = log_dir {
+        let saved: HashMap<String, WatcherState> = db
+            .watcher_states()
+            .get_states()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| (s.log_path.clone(), s))
+            .collect();
 
-                    if let Some(ts) = extract_timestamp(&line) {
-                        if ts != last_seen_timestamp {
-                            last_seen_timestamp = ts;
-                        }
-                    }
+        for path in list_log_files(dir) {
+            if tails.contains_key(&path) {
+                continue;
+            }
+            let saved_state = saved.get(path.to_string_lossy().as_ref());
+            let len = path.metadata().map(|m| m.len()).unwrap_or(0);
+            let has_new_data = saved_state.is_none_or(|s| len > s.last_position);
+            let is_latest = latest.as_ref() == Some(&path);
 
-                    if let Some(payload) = parse_log_line(&line) {
-                        match payload.event {
-                            VrcLogEvent::AppStart => {
-                                is_app_running = true;
-                                state_changed = true;
-                            }
-                            VrcLogEvent::AppStop => {
-                                is_app_running = false;
-                                state_changed = true;
-                            }
-                            _ => {}
-                        }
-                        let _ = LogPayload::emit(&payload, &app);
-                        let _ = db.logs().insert_log(&payload).await;
-                    }
+            if (is_latest && saved_state.is_none())
+                || (has_new_data && idle_duration(&path) < ACTIVE_WINDOW)
+            {
+                println!("Start watching log file: {:?}", path);
+                tails.insert(path.clone(), LogTail::open(path, saved_state));
+            }
+        }
+    }
 
-                    if let Ok(mut status) = shared_status.write() {
-                        status.is_app_running = is_app_running;
-                        status.last_seen_timestamp = last_seen_timestamp; // 💡 clone不要
-                    }
-                    line.clear();
-                    read_success = true;
-                }
-                Err(e) => eprintln!("Error reading log: {}", e),
+    let finished: Vec<PathBuf> = tails
+        .iter()
+        .filter(|(path, tail)| latest.as_ref() != Some(*path) && tail.is_finished())
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    for path in finished {
+        if let Some(mut tail) = tails.remove(&path) {
+            println!("Stop watching log file: {:?}", path);
+            for payload in tail.finish() {
+                publish(sink, payload, buffer);
             }
+            flush(db, buffer, [&mut tail], diag).await;
+            diag.close(&tail);
+            sink.closed(db, path, tail.utc_offset_secs(), tail.utc_offsets().clone());
         }
+    }
+}
+
+/// 状態変化を通知する最短間隔 (読み込み中は行数が毎回変わるため)
+const STATUS_EMIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 最後に行を読んだファイルを「現在のファイル」とする
+fn current_tail(tails: &HashMap<PathBuf, LogTail>) -> Option<&LogTail> {
+    tails
+        .values()
+        .max_by_key(|t| (t.last_seen_timestamp, t.position))
+}
+
+/// WatcherCommand で切り替わる状態
+#[derive(Default)]
+struct Control {
+    paused: bool,
+    pinned: Option<PathBuf>,
+}
+
+fn update_status(
+    sink: &dyn WatchSink,
+    shared_status: &Arc<RwLock<WatcherStatus>>,
+    tails: &HashMap<PathBuf, LogTail>,
+    control: &Control,
+    diag: &mut Diagnostics,
+) {
+    let current = current_tail(tails);
+
+    let lines_read = diag.closed_lines_read + tails.values().map(|t| t.lines_read).sum::<u64>();
+    let matched_lines =
+        diag.closed_matched_lines + tails.values().map(|t| t.matched_lines).sum::<u64>();
+
+    let Ok(mut status) = shared_status.write() else {
+        return;
+    };
+    // プロセスを監視できればそちらを正とする (クラッシュしてもすぐ分かる)
+    status.is_app_running = match diag.vrc_processes {
+        Some(count) => count > 0,
+        None => tails.values().any(|t| t.is_app_running),
+    };
+    status.last_seen_timestamp = tails
+        .values()
+        .map(|t| t.last_seen_timestamp)
+        .max()
+        .unwrap_or(status.last_seen_timestamp);
+    status.current_file = current.map(|t| t.path.to_string_lossy().to_string());
+    status.position = current.map_or(0, |t| t.position);
+    status.watched_files = tails.len() as u32;
+    status.lines_read = lines_read;
+    status.matched_lines = matched_lines;
+    status.unmatched_lines = lines_read - matched_lines;
+    status.malformed_lines =
+        diag.closed_malformed_lines + tails.values().map(|t| t.malformed_lines).sum::<u64>();
+    status.last_error = diag.last_error.clone();
+    status.paused = control.paused;
+    status.pinned_file = control
+        .pinned
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string());
+    status.refresh_lag();
+
+    let Some(last) = &diag.last_emitted else {
+        diag.last_emitted = Some(status.clone());
+        diag.last_emit = Some(Instant::now());
+        sink.status(&status);
+        return;
+    };
+    if *last == *status {
+        return;
+    }
+    // 起動/終了・ファイル切り替え・エラーはすぐに、それ以外は間引いて送る
+    let important = last.is_app_running != status.is_app_running
+        || last.paused != status.paused
+        || last.pinned_file != status.pinned_file
+        || last.current_file != status.current_file
+        || last.last_error != status.last_error;
+    let throttled = diag
+        .last_emit
+        .is_some_and(|t| t.elapsed() < STATUS_EMIT_INTERVAL);
+    if important || !throttled {
+        diag.last_emitted = Some(status.clone());
+        diag.last_emit = Some(Instant::now());
+        sink.status(&status);
+    }
+}
+
+/// 追跡中のファイルを全て閉じる。再開時に続きから読めるよう位置だけ保存し、
+/// まだ書き込みが続いているかもしれないので finish (InvalidAppStop の判定) はしない
+async fn detach_tails(
+    db: &DB,
+    tails: &mut HashMap<PathBuf, LogTail>,
+    buffer: &mut WriteBuffer,
+    diag: &mut Diagnostics,
+) {
+    flush(db, buffer, tails.values_mut(), diag).await;
+    for (_, tail) in tails.drain() {
+        diag.close(&tail);
+    }
+}
 
-        if state_changed || (read_success && last_db_sync.elapsed() > Duration::from_secs(5)) {
-            if let Some(path) = &current_log_path {
-                let state = WatcherState {
-                    log_path: path.to_string_lossy().to_string(),
-                    is_running: is_app_running,
-                    last_timestamp: i64_to_str(last_seen_timestamp), // 💡 DB保存用に文字列化
-                    last_position: current_position,
-                };
-                let _ = db.settings().save_watcher_state(&state).await;
-                last_db_sync = Instant::now();
+async fn handle_command(
+    command: WatcherCommand,
+    sink: &dyn WatchSink,
+    db: &DB,
+    tails: &mut HashMap<PathBuf, LogTail>,
+    control: &mut Control,
+    buffer: &mut WriteBuffer,
+    diag: &mut Diagnostics,
+    log_dir: Option<&Path>,
+) {
+    println!("Watcher command: {:?}", command);
+    match command {
+        WatcherCommand::Pause => {
+            flush(db, buffer, tails.values_mut(), diag).await;
+            control.paused = true;
+        }
+        WatcherCommand::Resume => control.paused = false,
+        WatcherCommand::Rescan => {
+            let path = control
+                .pinned
+                .clone()
+                .or_else(|| current_tail(tails).map(|t| t.path.clone()));
+            let Some(path) = path else {
+                diag.last_error = Some("No log file to rescan".to_string());
+                return;
+            };
+            if let Some(old) = tails.remove(&path) {
+                diag.close(&old);
+            }
+            tails.insert(path.clone(), LogTail::open(path, None));
+        }
+        WatcherCommand::SwitchTo(Some(path)) => {
+            if !path.is_file() {
+                diag.last_error = Some(format!("Log file not found: {:?}", path));
+                return;
             }
+            detach_tails(db, tails, buffer, diag).await;
+            tails.insert(path.clone(), LogTail::open(path.clone(), None));
+            control.pinned = Some(path);
         }
+        WatcherCommand::SwitchTo(None) => {
+            if control.pinned.take().is_some() {
+                detach_tails(db, tails, buffer, diag).await;
+                *tails = resume_tails(db).await;
+                refresh_tails(sink, db, tails, log_dir, buffer, diag).await;
+            }
+        }
+    }
+}
 
-        if read_success {
-            continue;
+/// プロセスの終了を、そのプロセスが書いていたログに伝える
+fn handle_process_update(
+    update: ProcessUpdate,
+    tails: &mut HashMap<PathBuf, LogTail>,
+    processes: &mut Vec<VrcProcess>,
+    diag: &mut Diagnostics,
+) {
+    diag.vrc_processes = Some(update.running.len());
+    *processes = update.running;
+    for change in update.changes {
+        if let ProcessChange::Stopped {
+            process,
+            stopped_at,
+        } = change
+        {
+            mark_process_exit(tails, &process, stopped_at, update.running.len());
         }
+    }
+}
 
-        tokio::select! {
-            _ = tokio::time::sleep(Duration::from_millis(500)) => {}
-            _ = rotation_check_interval.tick() => {
-                let latest = get_latest_log_path();
-
-                if latest != current_log_path {
-                    println!("Log rotation detected!");
-                    if is_app_running {
-                        let crash_payload = create_invalid_app_stop_payload(last_seen_timestamp);
-                        let _ = db.logs().insert_log(&crash_payload).await;
-                        let _ = LogPayload::emit(&crash_payload, &app);
-                    }
+fn mark_process_exit(
+    tails: &mut HashMap<PathBuf, LogTail>,
+    process: &VrcProcess,
+    stopped_at: i64,
+    remaining: usize,
+) {
+    let running = tails
+        .values_mut()
+        .filter(|t| t.is_app_running && t.exited_at.is_none());
+    if remaining == 0 {
+        // 全て終了していれば、起動中のままのログは全てこの時点で終わっている
+        for tail in running {
+            tail.mark_exited(stopped_at);
+        }
+        return;
+    }
+    if let Some(tail) = closest_to_process(running.map(|t| (t.created_at(), t)), process) {
+        tail.mark_exited(stopped_at);
+    }
+}
 
-                    current_log_path = latest.clone();
-                    is_app_running = false;
-                    current_position = 0;
+/// 複数起動している場合は、ファイル名の作成時刻がプロセスの開始時刻に最も近いログとみなす
+fn closest_to_process<T>(
+    candidates: impl Iterator<Item = (Option<i64>, T)>,
+    process: &VrcProcess,
+) -> Option<T> {
+    candidates
+        .filter_map(|(created_at, t)| Some(((created_at? - process.started_at).abs(), t)))
+        .filter(|(diff, _)| *diff <= PROCESS_LOG_MATCH_WINDOW_MS)
+        .min_by_key(|(diff, _)| *diff)
+        .map(|(_, t)| t)
+}
 
-                    if let Ok(mut status) = shared_status.write() {
-                        status.is_app_running = is_app_running;
-                        status.last_seen_timestamp = last_seen_timestamp; // 💡 clone不要
-                    }
+/// 起動中のプロセスごとに、書いているログのアカウントを process_samples の記録用に共有する
+fn link_process_accounts(
+    processes: &[VrcProcess],
+    tails: &HashMap<PathBuf, LogTail>,
+    accounts: &ProcessAccounts,
+) {
+    let running: Vec<&LogTail> = tails
+        .values()
+        .filter(|t| t.is_app_running && t.exited_at.is_none())
+        .collect();
+    let linked: HashMap<u32, String> = processes
+        .iter()
+        .filter_map(|process| {
+            // 1つずつしか無ければ、ファイル名の時刻に関係なく対応している
+            let tail = if processes.len() == 1 && running.len() == 1 {
+                Some(running[0])
+            } else {
+                closest_to_process(running.iter().map(|t| (t.created_at(), *t)), process)
+            };
+            Some((process.pid, tail?.account.clone()?))
+        })
+        .collect();
+    if let Ok(mut shared) = accounts.write() {
+        *shared = linked;
+    }
+}
 
-                    if let Some(path) = &current_log_path {
-                        let _ = db.settings().save_watcher_state(&WatcherState {
-                            log_path: path.to_string_lossy().to_string(),
-                            is_running: false,
-                            last_timestamp: i64_to_str(last_seen_timestamp), // 💡 DB保存用に文字列化
-                            last_position: 0,
-                        }).await;
-                    }
+async fn watch_loop(
+    sink: impl WatchSink,
+    db: DB,
+    shared_status: Arc<RwLock<WatcherStatus>>,
+    mut cmd_rx: tokio::sync::mpsc::UnboundedReceiver<WatcherCommand>,
+    mut process_rx: tokio::sync::mpsc::UnboundedReceiver<ProcessUpdate>,
+    process_accounts: ProcessAccounts,
+    safety_poll: Duration,
+    shutdown: CancellationToken,
+) {
+    if let Err(e) = reload_custom_rules(&db).await {
+        eprintln!("Failed to load custom rules: {}", e);
+    }
+
+    let mut rotation_check_interval = tokio::time::interval(Duration::from_secs(5));
+    let log_dir = get_vrc_log_dir(&db).await;
+
+    // ファイル変更通知 (届けばすぐに読む)。通知を使える間は safety_poll ごと、
+    // 使えない間は POLL_INTERVAL ごとに読み直す。watcher は保持している間だけ通知を送る
+    let (fs_tx, mut fs_rx) = tokio::sync::mpsc::unbounded_channel::<FsNotification>();
+    let mut fs_watcher = log_dir
+        .as_deref()
+        .and_then(|dir| create_fs_watcher(dir, fs_tx.clone()));
+    let mut fs_failed = false;
+    let mut watched_dir = log_dir;
 
-                    if let Some(path) = latest {
-                        match File::open(&path) {
-                            Ok(f) => {
-                                reader = Some(BufReader::new(f));
-                            }
-                            Err(_) => reader = None,
+    // VRChat は複数クライアントを同時に起動でき、それぞれが別の output_log に書き込む。
+    // 書き込みが続いているファイルは全て並行して追跡する
+    let mut diag = Diagnostics::default();
+    if watched_dir.is_none() {
+        diag.last_error = Some("VRChat log directory not found".to_string());
+    }
+    let mut buffer = WriteBuffer::new();
+    let mut tails = resume_tails(&db).await;
+    refresh_tails(
+        &sink,
+        &db,
+        &mut tails,
+        watched_dir.as_deref(),
+        &mut buffer,
+        &mut diag,
+    )
+    .await;
+    if tails.is_empty() {
+        println!("No VRChat log file found yet.");
+    }
+    let mut control = Control::default();
+    let mut processes: Vec<VrcProcess> = Vec::new();
+
+    while !shutdown.is_cancelled() {
+        // 読み込み中 (select を通らない間) に届いた操作もここで処理する
+        while let Ok(command) = cmd_rx.try_recv() {
+            handle_command(
+                command,
+                &sink,
+                &db,
+                &mut tails,
+                &mut control,
+                &mut buffer,
+                &mut diag,
+                watched_dir.as_deref(),
+            )
+            .await;
+        }
+        while let Ok(update) = process_rx.try_recv() {
+            handle_process_update(update, &mut tails, &mut processes, &mut diag);
+        }
+
+        let mut read_any = false;
+        let mut force_flush = false;
+
+        // 一時停止中は読み進めない
+        if !control.paused {
+            match read_pass(std::mem::take(&mut tails)).await {
+                Ok((returned, pass)) => {
+                    tails = returned;
+                    read_any = pass.read_any;
+                    // 起動・終了・ログインは状態が変わるのですぐに書き込む
+                    force_flush = pass.force_flush;
+                    if pass.last_error.is_some() {
+                        diag.last_error = pass.last_error;
+                    }
+                    for payload in pass.payloads {
+                        if let VrcLogEvent::Screenshot { path } = &payload.event {
+                            screenshots::spawn_index_logged(
+                                db.clone(),
+                                path.clone(),
+                                payload.timestamp,
+                                payload.account.clone(),
+                            );
                         }
+                        publish(&sink, payload, &mut buffer);
                     }
                 }
+                Err(e) => {
+                    // 読み込みスレッドが落ちた場合は、保存済みの位置から開き直す
+                    eprintln!("Log reader failed: {}", e);
+                    diag.last_error = Some(format!("Log reader failed: {}", e));
+                    tails = resume_tails(&db).await;
+                }
+            }
+        }
+
+        // 読み切った時点、または読み続けている間も件数・時間ごとに書き込む
+        if force_flush || !read_any || buffer.is_due() {
+            flush(&db, &mut buffer, tails.values_mut(), &mut diag).await;
+        }
+
+        link_process_accounts(&processes, &tails, &process_accounts);
+        update_status(&sink, &shared_status, &tails, &control, &mut diag);
+
+        if read_any {
+            continue;
+        }
+
+        let poll_interval = if fs_watcher.is_some() && !fs_failed {
+            safety_poll
+        } else {
+            POLL_INTERVAL
+        };
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(poll_interval) => {}
+            Some(command) = cmd_rx.recv() => {
+                handle_command(
+                    command,
+                    &sink,
+                    &db,
+                    &mut tails,
+                    &mut control,
+                    &mut buffer,
+                    &mut diag,
+                    watched_dir.as_deref(),
+                )
+                .await;
+            }
+            Some(update) = process_rx.recv() => {
+                handle_process_update(update, &mut tails, &mut processes, &mut diag);
+            }
+            Some(notification) = fs_rx.recv() => {
+                // 溜まっている通知はまとめて処理する
+                let mut new_log_created = notification == FsNotification::Created;
+                fs_failed |= notification == FsNotification::Failed;
+                while let Ok(n) = fs_rx.try_recv() {
+                    new_log_created |= n == FsNotification::Created;
+                    fs_failed |= n == FsNotification::Failed;
+                }
+                if new_log_created {
+                    rotation_check_interval.reset_immediately();
+                }
+            }
+            _ = rotation_check_interval.tick() => {
+                // 設定変更に追従するため、毎回ディレクトリを解決し直す
+                let log_dir = get_vrc_log_dir(&db).await;
+                // エラーの届いた watcher は作り直す (作れるまでは POLL_INTERVAL で読む)
+                if log_dir != watched_dir || fs_failed {
+                    drop(fs_watcher.take());
+                    fs_watcher = log_dir
+                        .as_deref()
+                        .and_then(|dir| create_fs_watcher(dir, fs_tx.clone()));
+                    fs_failed = false;
+                }
+                if log_dir != watched_dir {
+                    println!("Log directory changed: {:?}", log_dir);
+                    watched_dir = log_dir;
+                    diag.last_error = watched_dir
+                        .is_none()
+                        .then(|| "VRChat log directory not found".to_string());
+                }
+
+                // 一時停止中・ファイル固定中はローテーションに追従しない
+                if !control.paused && control.pinned.is_none() {
+                    refresh_tails(
+                        &sink,
+                        &db,
+                        &mut tails,
+                        watched_dir.as_deref(),
+                        &mut buffer,
+                        &mut diag,
+                    )
+                    .await;
+                }
+                update_status(&sink, &shared_status, &tails, &control, &mut diag);
             }
         }
     }
+
+    // 終了時: 溜まっているログと、最後の読み込み位置・起動状態を書き込む
+    flush(&db, &mut buffer, tails.values_mut(), &mut diag).await;
+    println!("Log watcher stopped. (pending writes flushed)");
 }
 
 // ================================================================
 // Public Entry Point
 // ================================================================
 
-pub fn spawn_log_watcher(app: AppHandle, db: DB) -> WatcherService {
-    let shared_status = Arc::new(RwLock::new(WatcherStatus {
-        is_app_running: false,
-        last_seen_timestamp: 0, // 💡 0で初期化
-    }));
+/// `shutdown` がキャンセルされると、書き込みを終えてから停止する
+pub fn spawn_log_watcher(app: AppHandle, db: DB, shutdown: CancellationToken) -> WatcherService {
+    let shared_status = Arc::new(RwLock::new(WatcherStatus::default()));
+    let (cmd_tx, cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+    let process_accounts = ProcessAccounts::default();
+    let process_rx = process::spawn_process_monitor(
+        app.clone(),
+        db.clone(),
+        Arc::clone(&process_accounts),
+        shutdown.clone(),
+    );
 
     WatcherService {
-        handle: tauri::async_runtime::spawn(watch_loop(app, db, Arc::clone(&shared_status))),
+        handle: Mutex::new(Some(tauri::async_runtime::spawn(watch_loop(
+            app,
+            db,
+            Arc::clone(&shared_status),
+            cmd_rx,
+            process_rx,
+            process_accounts,
+            SAFETY_POLL_INTERVAL,
+            shutdown,
+        )))),
         status: shared_status,
+        commands: cmd_tx,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parses_usharp_video_with_color_tag() {
+        let line = "2024.05.12 21:03:44 Log        -  [<color=#9C6994>USharpVideo</color>] Started video load for URL: https://www.youtube.com/watch?v=dQw4w9WgXcQ, requested by amgr";
+        let payload = parse_log_line(line).expect("line should match");
+        match payload.event {
+            VrcLogEvent::VideoPlay { url, requested_by } => {
+                assert_eq!(url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+                assert_eq!(requested_by.as_deref(), Some("amgr"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_usharp_video_without_requester() {
+        for line in [
+            "2024.05.12 21:03:44 Log        -  [<color=#9C6994>USharpVideo</color>] Started video load for URL: https://example.com/a.mp4",
+            "2024.05.12 21:03:44 Log        -  <color=#9C6994>[USharpVideo]</color> Started video load for URL: https://example.com/a.mp4",
+            "2024.05.12 21:03:44 Log        -  [USharpVideo] Started video load for URL: https://example.com/a.mp4",
+        ] {
+            let payload = parse_log_line(line).expect("line should match");
+            match payload.event {
+                VrcLogEvent::VideoPlay { url, requested_by } => {
+                    assert_eq!(url, "https://example.com/a.mp4");
+                    assert_eq!(requested_by, None);
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_builtin_rules_that_win_over_a_custom_rule() {
+        let line =
+            "2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Friend (usr_friend)";
+        let result =
+            test_custom_rule(r"\[Behaviour\] OnPlayerJoined (?P<name>.+) \(", line).unwrap();
+        assert_eq!(
+            result.fields.unwrap().get("name").map(String::as_str),
+            Some("Friend")
+        );
+        assert_eq!(result.shadowed_by.as_deref(), Some("PlayerJoin"));
+
+        let line = "2026.10.18 12:00:00 Log        -  [QuestSystem] completed: Find the key";
+        let result = test_custom_rule(r"\[QuestSystem\] completed: (?P<quest>.+)", line).unwrap();
+        assert!(result.fields.is_some());
+        assert_eq!(result.shadowed_by, None);
+
+        let result = test_custom_rule(r"\[NoSuchSystem\] (?P<x>.+)", line).unwrap();
+        assert!(result.fields.is_none());
+        assert_eq!(result.shadowed_by, None);
+    }
+
+    #[test]
+    fn reports_custom_rules_saved_before_the_tested_one() {
+        let rule = |name: &str, pattern: &str| CompiledMatcher {
+            regex: compile_custom_rule(pattern).unwrap(),
+            kind: MatcherKind::Custom {
+                rule: name.to_string(),
+            },
+        };
+        let matchers = build_matchers(vec![
+            rule("Any quest", r"\[QuestSystem\] (?P<message>.+)"),
+            rule("Completed", r"\[QuestSystem\] completed: (?P<quest>.+)"),
+        ]);
+        let line = "2026.10.18 12:00:00 Log        -  [QuestSystem] completed: Find the key";
+
+        let completed = compile_custom_rule(r"\[QuestSystem\] completed: (?P<quest>.+)").unwrap();
+        assert_eq!(
+            shadowing_rule(&matchers, &completed, line).as_deref(),
+            Some("Any quest")
+        );
+        // 保存済みのルール自身は、それより前のルールだけと比べる
+        let any = compile_custom_rule(r"\[QuestSystem\] (?P<message>.+)").unwrap();
+        assert_eq!(shadowing_rule(&matchers, &any, line), None);
+    }
+
+    #[test]
+    fn parses_avatar_switches_with_spaces_in_names() {
+        let line = "2026.10.18 12:00:00 Log        -  [Behaviour] Switching Some Player to avatar Cool Avatar (Quest)";
+        let payload = parse_log_line(line).expect("line should match");
+        match payload.event {
+            VrcLogEvent::AvatarChange {
+                player_name,
+                avatar_name,
+            } => {
+                assert_eq!(player_name, "Some Player");
+                assert_eq!(avatar_name, "Cool Avatar (Quest)");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    /// 受け取ったイベントをチャネルに流す
+    struct ChannelSink(tokio::sync::mpsc::UnboundedSender<LogPayload>);
+
+    impl WatchSink for ChannelSink {
+        fn log(&self, payload: &LogPayload) {
+            let _ = self.0.send(payload.clone());
+        }
+
+        fn status(&self, _status: &WatcherStatus) {}
+
+        fn closed(
+            &self,
+            _db: &DB,
+            _path: PathBuf,
+            _utc_offset: Option<i32>,
+            _utc_offsets: UtcOffsetRanges,
+        ) {
+        }
+    }
+
+    fn append(path: &Path, text: &str) {
+        use std::io::Write;
+        let mut f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        f.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn delivers_appended_lines_by_file_notification() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::new(dir.path().join("data")).await.unwrap();
+        let log_dir = dir.path().join("logs");
+        fs::create_dir(&log_dir).unwrap();
+        db.settings()
+            .set_log_dir(Some(&log_dir.to_string_lossy()))
+            .await
+            .unwrap();
+        let log_path = log_dir.join("output_log_2026-10-18_12-00-00.txt");
+        append(
+            &log_path,
+            "2026.10.18 12:00:00 Log        -  VRCNP: Server started\n",
+        );
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let (_cmd_tx, cmd_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (_process_tx, process_rx) = tokio::sync::mpsc::unbounded_channel();
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(watch_loop(
+            ChannelSink(tx),
+            db.clone(),
+            Arc::new(RwLock::new(WatcherStatus::default())),
+            cmd_rx,
+            process_rx,
+            ProcessAccounts::default(),
+            // 読み直しでは届かないようにして、変更通知だけで届くことを確かめる
+            Duration::from_secs(60 * 60),
+            shutdown.clone(),
+        ));
+
+        let first = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("existing line was not delivered")
+            .unwrap();
+        assert!(matches!(first.event, VrcLogEvent::AppStart));
+
+        // ディレクトリの確認 (5秒ごと) を待たずに、通知ですぐに届く
+        for i in 1..=5 {
+            let line = format!(
+                "2026.10.18 12:00:{:02} Log        -  [Behaviour] OnPlayerJoined Player{} (usr_{:04})\n",
+                i, i, i
+            );
+            let written = Instant::now();
+            append(&log_path, &line);
+            let payload = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("appended line was not delivered")
+                .unwrap();
+            let latency = written.elapsed();
+            match payload.event {
+                VrcLogEvent::PlayerJoin { player_name, .. } => {
+                    assert_eq!(player_name, format!("Player{}", i))
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+            assert!(
+                latency < Duration::from_secs(1),
+                "line {} took {:?} to be delivered",
+                i,
+                latency
+            );
+        }
+
+        shutdown.cancel();
+        handle.await.unwrap();
+    }
+
+    fn read_all(tail: &mut LogTail) -> Vec<LogPayload> {
+        let mut payloads = Vec::new();
+        loop {
+            match tail.read() {
+                TailRead::Payload(payload) => payloads.push(payload),
+                TailRead::Skipped => {}
+                TailRead::Eof => return payloads,
+            }
+        }
+    }
+
+    fn joined_name(payload: &LogPayload) -> &str {
+        match &payload.event {
+            VrcLogEvent::PlayerJoin { player_name, .. } => player_name,
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_crlf_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        let bytes = b"2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Alice (usr_alice)\r\n\
+                      2026.10.18 12:00:01 Log        -  [Behaviour] OnPlayerJoined Bob (usr_bob)\r\n";
+        fs::write(&path, bytes).unwrap();
+
+        let mut tail = LogTail::open(path, None);
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 2);
+        match &payloads[1].event {
+            VrcLogEvent::PlayerJoin {
+                player_name,
+                user_id,
+            } => {
+                assert_eq!(player_name, "Bob");
+                assert_eq!(user_id, "usr_bob");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert_eq!(tail.position, bytes.len() as u64);
+        assert_eq!(tail.malformed_lines, 0);
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_partial_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        let first =
+            b"2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Alice (usr_alice)\n";
+        let mut bytes = first.to_vec();
+        bytes.extend_from_slice(b"2026.10.18 12:00:01 Log        -  [Behaviour] OnPlayerJoined Bo");
+        fs::write(&path, &bytes).unwrap();
+
+        let mut tail = LogTail::open(path.clone(), None);
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(joined_name(&payloads[0]), "Alice");
+        // 書きかけの行は確定させない
+        assert_eq!(tail.position, first.len() as u64);
+
+        append(&path, "b (usr_bob)\n");
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(joined_name(&payloads[0]), "Bob");
+        assert_eq!(tail.position, fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn skips_over_invalid_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        let bytes = b"2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Al\xff\xfeice (usr_alice)\n\
+                      2026.10.18 12:00:01 Log        -  \xc3\x28 broken\n\
+                      2026.10.18 12:00:02 Log        -  [Behaviour] OnPlayerJoined Bob (usr_bob)\n";
+        fs::write(&path, bytes).unwrap();
+
+        let mut tail = LogTail::open(path, None);
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(joined_name(&payloads[0]), "Al\u{FFFD}\u{FFFD}ice");
+        assert_eq!(joined_name(&payloads[1]), "Bob");
+        assert_eq!(tail.malformed_lines, 2);
+        assert_eq!(tail.lines_read, 3);
+        assert_eq!(tail.position, bytes.len() as u64);
+    }
+
+    #[test]
+    fn rereads_a_truncated_and_rewritten_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        fs::write(
+            &path,
+            "2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Alice (usr_alice)\n\
+             2026.10.18 12:00:01 Log        -  [Behaviour] OnPlayerJoined Bob (usr_bob)\n",
+        )
+        .unwrap();
+
+        let mut tail = LogTail::open(path.clone(), None);
+        assert_eq!(read_all(&mut tail).len(), 2);
+
+        let rewritten =
+            "2026.10.18 13:00:00 Log        -  [Behaviour] OnPlayerJoined Carol (usr_carol)\n";
+        fs::write(&path, rewritten).unwrap();
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(joined_name(&payloads[0]), "Carol");
+        assert_eq!(tail.position, rewritten.len() as u64);
+    }
+
+    #[test]
+    fn rereads_recorded_lines_with_the_offset_used_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        fs::write(
+            &path,
+            "2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Alice (usr_alice)\n\
+             2026.10.18 12:00:01 Log        -  [Behaviour] OnPlayerJoined Bob (usr_bob)\n",
+        )
+        .unwrap();
+        let tokyo = FixedOffset::east_opt(9 * 60 * 60);
+        let new_york = FixedOffset::west_opt(5 * 60 * 60);
+
+        let mut tail = LogTail::open(path.clone(), None).with_utc_offset(tokyo);
+        let first: Vec<(i64, i64)> = read_all(&mut tail)
+            .iter()
+            .map(|p| (p.timestamp, p.hash))
+            .collect();
+        let saved = tail.utc_offsets().to_json();
+        assert!(saved.is_some());
+
+        // 別のタイムゾーンに移ってから読み直しても、記録済みの行は同じ時刻・ハッシュになる
+        append(
+            &path,
+            "2026.10.18 12:00:02 Log        -  [Behaviour] OnPlayerJoined Carol (usr_carol)\n",
+        );
+        let mut tail = LogTail::open(path, None)
+            .with_utc_offset(new_york)
+            .with_utc_offsets(UtcOffsetRanges::from_json(saved.as_deref()));
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 3);
+        let reread: Vec<(i64, i64)> = payloads[..2]
+            .iter()
+            .map(|p| (p.timestamp, p.hash))
+            .collect();
+        assert_eq!(reread, first);
+
+        // まだ読んでいなかった行は今のオフセットで変換する
+        let carol =
+            NaiveDateTime::parse_from_str("2026-10-18 12:00:02", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(
+            payloads[2].timestamp,
+            naive_to_i64(&carol, new_york.unwrap())
+        );
+        assert_eq!(
+            tail.utc_offsets().get(0),
+            tokyo,
+            "the offset of the first lines is kept"
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_numbering_of_duplicates_in_the_same_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::new(dir.path().join("data")).await.unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        let line =
+            "2026.10.18 12:00:00 Log        -  [Behaviour] OnPlayerJoined Alice (usr_alice)\n";
+        fs::write(&path, line.repeat(2)).unwrap();
+
+        let mut tail = LogTail::open(path.clone(), None);
+        let expected: Vec<i64> = read_all(&mut tail).iter().map(|p| p.hash).collect();
+        assert_eq!(expected.len(), 2);
+        assert_ne!(expected[0], expected[1]);
+
+        // 1行目まで読んだところでアプリを終了し、保存した状態から再開する
+        let mut tail = LogTail::open(path.clone(), None);
+        let TailRead::Payload(first) = tail.read() else {
+            panic!("first line was not read");
+        };
+        assert_eq!(first.hash, expected[0]);
+        db.watcher_states().save_state(&tail.state()).await.unwrap();
+
+        let states = db.watcher_states().get_states().await.unwrap();
+        let mut tail = LogTail::open(path, states.first());
+        let payloads = read_all(&mut tail);
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].hash, expected[1]);
+    }
+
+    /// 同じインスタンスに同じ秒に入った、別アカウントのクライアントのログ
+    fn client_log(name: &str, user_id: &str) -> String {
+        format!(
+            "2026.10.18 12:00:00 Log        -  User Authenticated: {name} ({user_id})\n\
+             2026.10.18 12:00:10 Log        -  [Behaviour] Entering Room: Test World\n\
+             2026.10.18 12:00:11 Log        -  [Behaviour] Joining wrld_00000000-0000-0000-0000-000000000000:12345~private(usr_main)\n\
+             2026.10.18 12:00:12 Log        -  [Behaviour] OnPlayerJoined Main (usr_main)\n\
+             2026.10.18 12:00:12 Log        -  [Behaviour] OnPlayerJoined Alt (usr_alt)\n"
+        )
+    }
+
+    #[tokio::test]
+    async fn keeps_events_of_each_account_in_the_same_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = DB::new(dir.path().join("data")).await.unwrap();
+
+        let mut payloads = Vec::new();
+        for (i, (name, user_id)) in [("Main", "usr_main"), ("Alt", "usr_alt")]
+            .into_iter()
+            .enumerate()
+        {
+            let path = dir
+                .path()
+                .join(format!("output_log_2026-10-18_11-59-5{}.txt", i));
+            fs::write(&path, client_log(name, user_id)).unwrap();
+            let file_payloads = read_all(&mut LogTail::open(path, None));
+            assert!(file_payloads
+                .iter()
+                .all(|p| p.account.as_deref() == Some(user_id)));
+            payloads.extend(file_payloads);
+        }
+        assert_eq!(payloads.len(), 10);
+
+        // 同じ行でもアカウントが違えば重複として捨てない
+        let inserted = db.logs().insert_batch(&payloads, &[]).await.unwrap();
+        assert_eq!(inserted, 10);
+
+        let logs = db
+            .logs()
+            .get_session_expanded_logs(None, None)
+            .await
+            .unwrap();
+        let sessions = crate::cmds::vrclog::sessions::build_sessions(logs, i64::MAX);
+        let mut usernames: Vec<_> = sessions.iter().map(|s| s.username.clone()).collect();
+        usernames.sort();
+        assert_eq!(
+            usernames,
+            [Some("Alt".to_string()), Some("Main".to_string())]
+        );
+    }
+
+    #[test]
+    fn links_each_process_to_the_account_of_its_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tails = HashMap::new();
+        let mut processes = Vec::new();
+        for (pid, file_time, name, user_id) in [
+            (100, "2026-10-18_11-00-00", "Main", "usr_main"),
+            (200, "2026-10-18_12-00-00", "Alt", "usr_alt"),
+        ] {
+            let path = dir.path().join(format!("output_log_{}.txt", file_time));
+            fs::write(
+                &path,
+                format!(
+                    "2026.10.18 12:00:00 Log        -  VRCNP: Server started\n{}",
+                    client_log(name, user_id)
+                ),
+            )
+            .unwrap();
+            let mut tail = LogTail::open(path.clone(), None);
+            read_all(&mut tail);
+            processes.push(VrcProcess {
+                pid,
+                started_at: tail.created_at().unwrap() + 2_000,
+            });
+            tails.insert(path, tail);
+        }
+
+        let accounts = ProcessAccounts::default();
+        link_process_accounts(&processes, &tails, &accounts);
+        let accounts = accounts.read().unwrap();
+        assert_eq!(accounts.get(&100).map(String::as_str), Some("usr_main"));
+        assert_eq!(accounts.get(&200).map(String::as_str), Some("usr_alt"));
+    }
+
+    fn touch(path: &Path, mtime: NaiveDateTime) {
+        let mtime = Local.from_local_datetime(&mtime).unwrap();
+        let f = File::create(path).unwrap();
+        f.set_modified(mtime.into()).unwrap();
+    }
+
+    fn local(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn names(paths: &[PathBuf]) -> Vec<&str> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn prefers_the_time_in_the_file_name_over_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        // 後から書き込まれた (mtime が新しい) が、名前の時刻は古いファイル
+        let older = dir.path().join("output_log_2026-10-18_10-00-00.txt");
+        let newer = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        touch(&older, local("2026-10-18 13:00:00"));
+        touch(&newer, local("2026-10-18 12:30:00"));
+
+        assert_eq!(log_sort_time(&older), Some(local("2026-10-18 10:00:00")));
+        let mut logs = list_log_files(dir.path());
+        sort_log_files(&mut logs);
+        assert_eq!(
+            names(&logs),
+            [
+                "output_log_2026-10-18_10-00-00.txt",
+                "output_log_2026-10-18_12-00-00.txt"
+            ]
+        );
+        assert_eq!(get_latest_log_path(dir.path()), Some(newer));
+    }
+
+    #[test]
+    fn falls_back_to_mtime_for_unmatched_names() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(
+            &dir.path().join("output_log.txt"),
+            local("2026-10-18 11:00:00"),
+        );
+        touch(
+            &dir.path().join("output_log_2026-10-18_12-00-00.txt"),
+            local("2026-10-18 12:30:00"),
+        );
+        touch(
+            &dir.path().join("output_log_renamed.txt"),
+            local("2026-10-18 13:00:00"),
+        );
+        // ログではないファイルは無視する
+        touch(&dir.path().join("Player.log"), local("2026-10-18 14:00:00"));
+
+        assert_eq!(
+            log_sort_time(&dir.path().join("output_log.txt")),
+            Some(local("2026-10-18 11:00:00"))
+        );
+        let mut logs = list_log_files(dir.path());
+        // 消えたなどでメタデータが取れないファイルは最も古いものとして扱う
+        logs.push(dir.path().join("output_log_missing.txt"));
+        sort_log_files(&mut logs);
+        assert_eq!(
+            names(&logs),
+            [
+                "output_log_missing.txt",
+                "output_log.txt",
+                "output_log_2026-10-18_12-00-00.txt",
+                "output_log_renamed.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn orders_files_with_the_same_mtime_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["output_log_b.txt", "output_log_a.txt", "output_log_c.txt"] {
+            touch(&dir.path().join(name), local("2026-10-18 12:00:00"));
+        }
+
+        let mut logs = list_log_files(dir.path());
+        sort_log_files(&mut logs);
+        assert_eq!(
+            names(&logs),
+            ["output_log_a.txt", "output_log_b.txt", "output_log_c.txt"]
+        );
+    }
+
+    #[test]
+    fn crash_on_process_exit_matches_the_reimported_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output_log_2026-10-18_12-00-00.txt");
+        fs::write(
+            &path,
+            "2026.10.18 12:00:00 Log        -  User Authenticated: Main (usr_main)\n\
+             2026.10.18 12:00:01 Log        -  VRCNP: Server started\n\
+             2026.10.18 12:05:00 Log        -  [Behaviour] OnPlayerJoined Main (usr_main)\n",
+        )
+        .unwrap();
+
+        // watcher: プロセスの終了を検知してから読み切る
+        let mut watched = LogTail::open(path.clone(), None);
+        read_all(&mut watched);
+        let stopped_at = watched.last_seen_timestamp + 60_000;
+        watched.mark_exited(stopped_at);
+        let crash = watched.take_exit().expect("crash should be reported");
+
+        // backfill / 再解析: 読み切ってから閉じる
+        let mut reimported = LogTail::open(path, None);
+        read_all(&mut reimported);
+        let reimported_crash = reimported.finish().pop().unwrap();
+
+        assert!(matches!(crash.event, VrcLogEvent::InvalidAppStop));
+        // 記録する時刻はプロセスの終了時刻、取り込み直した分は最後の行の時刻になるが、
+        // ハッシュが同じなので二重には記録されない
+        assert_eq!(crash.timestamp, stopped_at);
+        assert_eq!(reimported_crash.timestamp, reimported.last_seen_timestamp);
+        assert_eq!(crash.hash, reimported_crash.hash);
+        assert_eq!(crash.account.as_deref(), Some("usr_main"));
     }
 }
@@ -1,17 +1,73 @@
 // src/utils/date.rs
 
-use chrono::{Local, NaiveDateTime, TimeZone};
+use chrono::{FixedOffset, Local, LocalResult, NaiveDateTime, Offset, TimeZone};
 
-/// VRChatのログ文字列 ("YYYY.MM.DD HH:mm:ss" または "YYYY-MM-DD HH:mm:ss") を i64 (ミリ秒) に変換
-pub fn str_to_i64(ts: &str) -> i64 {
+/// VRChatのログ文字列 ("YYYY.MM.DD HH:mm:ss" または "YYYY-MM-DD HH:mm:ss") を日時にする (タイムゾーン無し)
+pub fn parse_log_time(ts: &str) -> Option<NaiveDateTime> {
     let normalized = ts.replace('.', "-");
-    if let Ok(ndt) = NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%d %H:%M:%S") {
-        // VRChatのログはローカル時間なので、Localとして解釈してUNIXミリ秒に変換
-        if let chrono::LocalResult::Single(dt) = Local.from_local_datetime(&ndt) {
-            return dt.timestamp_millis();
+    NaiveDateTime::parse_from_str(&normalized, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+/// この PC のローカル時間で、その日時に使われる UTC オフセット。
+/// 夏時間の切り替えで重複する時刻は早い方 (切り替え前)、存在しない時刻は切り替え前のオフセットを使い、
+/// 同じ入力には必ず同じ結果を返す
+pub fn resolve_local_offset(ndt: &NaiveDateTime) -> FixedOffset {
+    resolve_offset(&Local, ndt)
+}
+
+fn resolve_offset<Tz: TimeZone>(tz: &Tz, ndt: &NaiveDateTime) -> FixedOffset {
+    tz.offset_from_local_datetime(ndt)
+        .earliest()
+        .or_else(|| {
+            // 存在しない時刻 (夏時間の開始で飛ばされた1時間)
+            tz.offset_from_local_datetime(&(*ndt - chrono::Duration::days(1)))
+                .earliest()
+        })
+        .map(|o| o.fix())
+        .unwrap_or_else(|| tz.offset_from_utc_datetime(ndt).fix())
+}
+
+/// ログの1行の時刻を、この PC のローカル時間として i64 (UNIXミリ秒) にする。
+/// ログは時刻順に書かれるので、夏時間の終わりで重複する時刻は直前の行 (`not_before`) より
+/// 前に戻らない方を選ぶ。どちらでも戻る場合と存在しない時刻は resolve_local_offset と同じ
+pub fn local_to_i64(ndt: &NaiveDateTime, not_before: i64) -> i64 {
+    to_i64_not_before(&Local, ndt, not_before)
+}
+
+fn to_i64_not_before<Tz: TimeZone>(tz: &Tz, ndt: &NaiveDateTime, not_before: i64) -> i64 {
+    match tz.offset_from_local_datetime(ndt) {
+        LocalResult::Ambiguous(earliest, latest) => {
+            let early = naive_to_i64(ndt, earliest.fix());
+            if early >= not_before {
+                early
+            } else {
+                naive_to_i64(ndt, latest.fix())
+            }
         }
+        _ => naive_to_i64(ndt, resolve_offset(tz, ndt)),
+    }
+}
+
+/// ローカル日時を、指定したオフセットで i64 (UNIXミリ秒) に変換
+pub fn naive_to_i64(ndt: &NaiveDateTime, offset: FixedOffset) -> i64 {
+    (*ndt - offset).and_utc().timestamp_millis()
+}
+
+/// VRChatのログ文字列を i64 (ミリ秒) に変換 (この PC のローカル時間として解釈)
+pub fn str_to_i64(ts: &str) -> i64 {
+    match parse_log_time(ts) {
+        // VRChatのログはローカル時間なので、Localとして解釈してUNIXミリ秒に変換
+        Some(ndt) => naive_to_i64(&ndt, resolve_local_offset(&ndt)),
+        None => 0, // パース失敗時は 0
+    }
+}
+
+/// "+09:00" / "-05:00" / "UTC" 形式のタイムゾーン指定をパースする
+pub fn parse_utc_offset(s: &str) -> Option<FixedOffset> {
+    match s.trim() {
+        "UTC" | "utc" | "Z" => FixedOffset::east_opt(0),
+        other => other.parse().ok(),
     }
-    0 // パース失敗時は 0 (適宜エラーハンドリングしてもOK)
 }
 
 /// i64 (UNIXミリ秒) を "YYYY-MM-DD HH:mm:ss" 形式の文字列に変換
@@ -25,3 +81,60 @@ pub fn i64_to_str(ts: i64) -> String {
         "unknown".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    fn ts(s: &str) -> NaiveDateTime {
+        parse_log_time(s).unwrap()
+    }
+
+    fn utc(s: &str) -> i64 {
+        ts(s).and_utc().timestamp_millis()
+    }
+
+    #[test]
+    fn keeps_timestamps_monotonic_across_dst_end() {
+        // 2026-11-01 02:00 EDT (-4) に 01:00 EST (-5) へ戻る
+        let mut last = 0;
+        let mut convert = |s: &str| {
+            last = to_i64_not_before(&New_York, &ts(s), last);
+            last
+        };
+        assert_eq!(convert("2026.11.01 00:59:59"), utc("2026.11.01 04:59:59"));
+        assert_eq!(convert("2026.11.01 01:30:00"), utc("2026.11.01 05:30:00"));
+        assert_eq!(convert("2026.11.01 01:59:59"), utc("2026.11.01 05:59:59"));
+        // 2回目の 01:00〜01:59 は EST
+        assert_eq!(convert("2026.11.01 01:00:00"), utc("2026.11.01 06:00:00"));
+        assert_eq!(convert("2026.11.01 01:30:00"), utc("2026.11.01 06:30:00"));
+        assert_eq!(convert("2026.11.01 02:00:00"), utc("2026.11.01 07:00:00"));
+    }
+
+    #[test]
+    fn resolves_each_line_with_its_own_offset() {
+        // 夏時間の終わりを跨いでも、最初の行のオフセットを使い続けない
+        let before = to_i64_not_before(&New_York, &ts("2026.10.31 23:00:00"), 0);
+        let after = to_i64_not_before(&New_York, &ts("2026.11.01 23:00:00"), before);
+        assert_eq!(before, utc("2026.11.01 03:00:00"));
+        assert_eq!(after, utc("2026.11.02 04:00:00"));
+    }
+
+    #[test]
+    fn ambiguous_time_without_history_uses_the_earlier_offset() {
+        assert_eq!(
+            to_i64_not_before(&New_York, &ts("2026.11.01 01:30:00"), 0),
+            utc("2026.11.01 05:30:00")
+        );
+    }
+
+    #[test]
+    fn nonexistent_time_uses_the_offset_before_the_gap() {
+        // 2026-03-08 02:00 EST に 03:00 EDT へ進むため 02:30 は存在しない
+        assert_eq!(
+            to_i64_not_before(&New_York, &ts("2026.03.08 02:30:00"), 0),
+            utc("2026.03.08 07:30:00")
+        );
+    }
+}
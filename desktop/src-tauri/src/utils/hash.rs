@@ -0,0 +1,45 @@
+// src/utils/hash.rs
+
+use xxhash_rust::xxh3::xxh3_64;
+
+/// 現在のイベントハッシュのバージョン (logs.hash_version に保存する)
+/// - 1: std の DefaultHasher (Rust のバージョンで変わりうるため廃止)
+/// - 2: xxh3_64(timestamp LE bytes + event JSON)
+///   同じ秒に同一イベントが複数回出た場合、2回目以降は with_sequence で派生させる
+/// - 3: アカウントが分かっているイベントは account も入力に含める (event_hash_v3)。
+///   別アカウントのクライアントが同じインスタンスにいると同じ行を同じ秒に書くため
+pub const HASH_VERSION: i32 = 3;
+
+/// v2 ハッシュ。data は logs.data に保存されるイベントの JSON 文字列
+/// (マイグレーションからも呼ぶので、入力の形式は変更しないこと)
+pub fn event_hash_v2(timestamp: i64, data: &str) -> i64 {
+    let mut buf = Vec::with_capacity(8 + data.len());
+    buf.extend_from_slice(&timestamp.to_le_bytes());
+    buf.extend_from_slice(data.as_bytes());
+    xxh3_64(&buf) as i64
+}
+
+/// v3 ハッシュ。account (ログを書いたクライアントの user_id) が無ければ v2 と同じ値になる
+pub fn event_hash_v3(timestamp: i64, data: &str, account: Option<&str>) -> i64 {
+    let Some(account) = account else {
+        return event_hash_v2(timestamp, data);
+    };
+    let mut buf = Vec::with_capacity(8 + data.len() + 1 + account.len());
+    buf.extend_from_slice(&timestamp.to_le_bytes());
+    buf.extend_from_slice(data.as_bytes());
+    buf.push(0);
+    buf.extend_from_slice(account.as_bytes());
+    xxh3_64(&buf) as i64
+}
+
+/// 同じ秒に同一イベントが seq 回目 (0始まり) に出現した場合のハッシュ
+/// seq == 0 は元のハッシュのままなので、既存データとの互換性を保てる
+pub fn with_sequence(hash: i64, seq: u32) -> i64 {
+    if seq == 0 {
+        return hash;
+    }
+    let mut buf = [0u8; 12];
+    buf[..8].copy_from_slice(&hash.to_le_bytes());
+    buf[8..].copy_from_slice(&seq.to_le_bytes());
+    xxh3_64(&buf) as i64
+}
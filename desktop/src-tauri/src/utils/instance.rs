@@ -0,0 +1,193 @@
+// src/utils/instance.rs
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// インスタンスの公開範囲
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum InstanceAccessType {
+    Public,
+    FriendsPlus,
+    Friends,
+    InvitePlus,
+    Invite,
+    Group,
+    GroupPlus,
+    GroupPublic,
+}
+
+/// instance_id ("wrld_x:12345~private(usr_..)~region(jp)~nonce(..)") を分解した情報
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+pub struct InstanceInfo {
+    #[serde(rename = "worldId")]
+    pub world_id: String,
+    /// "12345" の部分 (インスタンス番号/名前)
+    pub name: String,
+    #[serde(rename = "accessType")]
+    pub access_type: InstanceAccessType,
+    /// インスタンスのオーナー (usr_... または grp_...)
+    #[serde(rename = "ownerId")]
+    pub owner_id: Option<String>,
+    pub region: Option<String>,
+    pub nonce: Option<String>,
+    #[serde(rename = "canRequestInvite")]
+    pub can_request_invite: bool,
+    pub strict: bool,
+}
+
+impl InstanceInfo {
+    /// instance_id をパースする (world_id が無い場合は None)
+    pub fn parse(instance_id: &str) -> Option<Self> {
+        let (world_id, rest) = instance_id.split_once(':')?;
+        if !world_id.starts_with("wrld_") {
+            return None;
+        }
+
+        let mut tags = rest.split('~');
+        let name = tags.next().unwrap_or_default().to_string();
+
+        let mut owner_id = None;
+        let mut region = None;
+        let mut nonce = None;
+        let mut can_request_invite = false;
+        let mut strict = false;
+        // タグ無しは Public
+        let mut base = None;
+        let mut group_access = None;
+
+        for tag in tags {
+            let (key, value) = match tag.split_once('(') {
+                Some((k, v)) => (k, v.strip_suffix(')').map(str::to_string)),
+                None => (tag, None),
+            };
+            match key {
+                "hidden" | "friends" | "private" | "group" => {
+                    base = Some(key);
+                    owner_id = value;
+                }
+                "groupAccessType" => group_access = value,
+                "region" => region = value,
+                "nonce" => nonce = value,
+                "canRequestInvite" => can_request_invite = true,
+                "strict" => strict = true,
+                _ => {}
+            }
+        }
+
+        let access_type = match base {
+            Some("hidden") => InstanceAccessType::FriendsPlus,
+            Some("friends") => InstanceAccessType::Friends,
+            Some("private") if can_request_invite => InstanceAccessType::InvitePlus,
+            Some("private") => InstanceAccessType::Invite,
+            Some("group") => match group_access.as_deref() {
+                Some("public") => InstanceAccessType::GroupPublic,
+                Some("plus") => InstanceAccessType::GroupPlus,
+                _ => InstanceAccessType::Group,
+            },
+            _ => InstanceAccessType::Public,
+        };
+
+        Some(Self {
+            world_id: world_id.to_string(),
+            name,
+            access_type,
+            owner_id,
+            region,
+            nonce,
+            can_request_invite,
+            strict,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORLD: &str = "wrld_4cf554b4-430c-4f8f-b53e-1f294eed230b";
+
+    fn parse(tags: &str) -> InstanceInfo {
+        InstanceInfo::parse(&format!("{}:{}", WORLD, tags)).expect("instance id should parse")
+    }
+
+    #[test]
+    fn parses_a_public_instance() {
+        let info = parse("12345~region(jp)");
+        assert_eq!(info.world_id, WORLD);
+        assert_eq!(info.name, "12345");
+        assert_eq!(info.access_type, InstanceAccessType::Public);
+        assert_eq!(info.owner_id, None);
+        assert_eq!(info.region.as_deref(), Some("jp"));
+        assert_eq!(info.nonce, None);
+    }
+
+    #[test]
+    fn parses_user_owned_access_types() {
+        let owner = "usr_c1644b5b-3ca4-45b4-97c6-a2a0de70d469";
+        for (tags, access_type) in [
+            (
+                format!("~hidden({})", owner),
+                InstanceAccessType::FriendsPlus,
+            ),
+            (format!("~friends({})", owner), InstanceAccessType::Friends),
+            (format!("~private({})", owner), InstanceAccessType::Invite),
+            (
+                format!("~private({})~canRequestInvite", owner),
+                InstanceAccessType::InvitePlus,
+            ),
+        ] {
+            let info = parse(&format!("77777{}~region(use)~nonce(abc-123)", tags));
+            assert_eq!(info.access_type, access_type, "{}", tags);
+            assert_eq!(info.owner_id.as_deref(), Some(owner));
+            assert_eq!(info.region.as_deref(), Some("use"));
+            assert_eq!(info.nonce.as_deref(), Some("abc-123"));
+        }
+    }
+
+    #[test]
+    fn parses_group_access_types() {
+        let group = "grp_0a1b2c3d-0000-4000-8000-000000000000";
+        for (access, access_type) in [
+            ("", InstanceAccessType::Group),
+            ("~groupAccessType(members)", InstanceAccessType::Group),
+            ("~groupAccessType(plus)", InstanceAccessType::GroupPlus),
+            ("~groupAccessType(public)", InstanceAccessType::GroupPublic),
+        ] {
+            let info = parse(&format!("Lobby~group({}){}~region(eu)", group, access));
+            assert_eq!(info.access_type, access_type, "{}", access);
+            assert_eq!(info.owner_id.as_deref(), Some(group));
+            assert_eq!(info.name, "Lobby");
+        }
+    }
+
+    #[test]
+    fn parses_flags_in_any_order() {
+        let info = parse("1~strict~region(jp)~canRequestInvite~private(usr_x)");
+        assert_eq!(info.access_type, InstanceAccessType::InvitePlus);
+        assert!(info.strict);
+        assert!(info.can_request_invite);
+        assert_eq!(info.owner_id.as_deref(), Some("usr_x"));
+    }
+
+    #[test]
+    fn rejects_ids_without_a_world() {
+        assert_eq!(InstanceInfo::parse(""), None);
+        assert_eq!(InstanceInfo::parse("12345~region(jp)"), None);
+        assert_eq!(InstanceInfo::parse("avtr_123:12345"), None);
+    }
+
+    #[test]
+    fn ignores_malformed_tags() {
+        // 閉じ括弧の無い値や未知のタグは捨て、分かる部分だけを使う
+        let info = parse("12345~region(jp~nonce()~unknown(x)~friends");
+        assert_eq!(info.access_type, InstanceAccessType::Friends);
+        assert_eq!(info.owner_id, None);
+        assert_eq!(info.region, None);
+        assert_eq!(info.nonce.as_deref(), Some(""));
+
+        let info = parse("");
+        assert_eq!(info.name, "");
+        assert_eq!(info.access_type, InstanceAccessType::Public);
+    }
+}
@@ -0,0 +1,127 @@
+// src/utils/logdir.rs
+
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// VRChat の Steam AppID
+const VRCHAT_APP_ID: &str = "438100";
+
+/// Windows 標準の VRChat ログディレクトリ (AppData/LocalLow/VRChat/VRChat)
+pub fn default_log_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|path| {
+        path.join("..")
+            .join("LocalLow")
+            .join("VRChat")
+            .join("VRChat")
+    })
+}
+
+/// Windows 標準の VRChat スクリーンショットの保存先 (Pictures/VRChat)
+pub fn default_screenshot_dir() -> Option<PathBuf> {
+    dirs::picture_dir().map(|path| path.join("VRChat"))
+}
+
+/// Proton の prefix 内のユーザーディレクトリ
+fn proton_user_dir(library: &Path) -> PathBuf {
+    library
+        .join("steamapps")
+        .join("compatdata")
+        .join(VRCHAT_APP_ID)
+        .join("pfx")
+        .join("drive_c")
+        .join("users")
+        .join("steamuser")
+}
+
+/// Proton の prefix 内にある VRChat ログディレクトリ
+fn proton_log_dir(library: &Path) -> PathBuf {
+    proton_user_dir(library)
+        .join("AppData")
+        .join("LocalLow")
+        .join("VRChat")
+        .join("VRChat")
+}
+
+/// Proton の prefix 内にある VRChat スクリーンショットの保存先
+fn proton_screenshot_dir(library: &Path) -> PathBuf {
+    proton_user_dir(library).join("Pictures").join("VRChat")
+}
+
+/// Steam 本体のインストール先候補 (native / flatpak / snap)
+fn steam_roots() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    vec![
+        home.join(".steam").join("steam"),
+        home.join(".steam").join("root"),
+        home.join(".local").join("share").join("Steam"),
+        home.join(".var")
+            .join("app")
+            .join("com.valvesoftware.Steam")
+            .join(".local")
+            .join("share")
+            .join("Steam"),
+        home.join("snap")
+            .join("steam")
+            .join("common")
+            .join(".local")
+            .join("share")
+            .join("Steam"),
+    ]
+}
+
+/// libraryfolders.vdf から追加のライブラリフォルダを読み取る
+fn steam_library_folders(root: &Path) -> Vec<PathBuf> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r#""path"\s+"(.+?)""#).unwrap());
+
+    let vdf = root.join("steamapps").join("libraryfolders.vdf");
+    let Ok(content) = fs::read_to_string(vdf) else {
+        return Vec::new();
+    };
+
+    re.captures_iter(&content)
+        .map(|caps| PathBuf::from(caps[1].replace("\\\\", "\\")))
+        .collect()
+}
+
+/// 実在する VRChat ログディレクトリの候補を列挙する
+pub fn find_log_dir_candidates() -> Vec<PathBuf> {
+    find_candidates(default_log_dir(), proton_log_dir)
+}
+
+/// 実在する VRChat スクリーンショットの保存先の候補を列挙する
+pub fn find_screenshot_dir_candidates() -> Vec<PathBuf> {
+    find_candidates(default_screenshot_dir(), proton_screenshot_dir)
+}
+
+/// Windows 標準の場所と、各 Steam ライブラリの Proton prefix 内の場所を列挙する
+fn find_candidates(default: Option<PathBuf>, proton: fn(&Path) -> PathBuf) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    let mut push = |path: PathBuf| {
+        if !path.is_dir() {
+            return;
+        }
+        // シンボリックリンク (~/.steam/steam 等) で同じ場所を指す場合は1つにまとめる
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        if !candidates.contains(&path) {
+            candidates.push(path);
+        }
+    };
+
+    if let Some(dir) = default {
+        push(dir);
+    }
+
+    for root in steam_roots() {
+        push(proton(&root));
+        for library in steam_library_folders(&root) {
+            push(proton(&library));
+        }
+    }
+
+    candidates
+}
@@ -1,2 +1,6 @@
 pub mod constants;
 pub mod date;
+pub mod hash;
+pub mod instance;
+pub mod logdir;
+pub mod png;
@@ -0,0 +1,245 @@
+// src/utils/png.rs
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// 仕様上のチャンク長の上限 (2^31 - 1)
+const MAX_CHUNK_LEN: u32 = 0x7fff_ffff;
+
+/// tEXt / 非圧縮の iTXt チャンクの内容
+#[derive(Debug, Clone)]
+pub struct TextChunk {
+    pub keyword: String,
+    pub text: String,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// チャンクの長さと種類を読む (ファイル末尾なら None)。
+/// 壊れたファイルや書き込み途中のファイルで巨大な領域を確保しないよう、
+/// ファイルに収まらない長さはエラーにする
+fn read_chunk_header(r: &mut impl Read, file_len: u64) -> io::Result<Option<(u32, [u8; 4])>> {
+    let mut header = [0u8; 8];
+    match r.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let kind = [header[4], header[5], header[6], header[7]];
+    if len > MAX_CHUNK_LEN || len as u64 > file_len {
+        return Err(invalid("Chunk length exceeds the file size"));
+    }
+    Ok(Some((len, kind)))
+}
+
+/// 先頭が PNG のシグネチャか
+pub fn has_signature(bytes: &[u8]) -> bool {
+    bytes.starts_with(&SIGNATURE)
+}
+
+fn check_signature(r: &mut impl Read) -> io::Result<()> {
+    let mut sig = [0u8; 8];
+    r.read_exact(&mut sig)?;
+    if sig != SIGNATURE {
+        return Err(invalid("Not a PNG file"));
+    }
+    Ok(())
+}
+
+/// "keyword\0text" (tEXt) または
+/// "keyword\0 圧縮フラグ 圧縮方式 言語タグ\0 翻訳キーワード\0 text" (iTXt) を分解する
+fn parse_text_chunk(kind: &[u8; 4], data: &[u8]) -> Option<TextChunk> {
+    let nul = data.iter().position(|b| *b == 0)?;
+    let keyword = String::from_utf8_lossy(&data[..nul]).to_string();
+    let rest = &data[nul + 1..];
+
+    let text = match kind {
+        // tEXt は Latin-1
+        b"tEXt" => rest.iter().map(|b| *b as char).collect(),
+        b"iTXt" => {
+            let (&compressed, rest) = rest.split_first()?;
+            if compressed != 0 {
+                return None;
+            }
+            let rest = rest.get(1..)?; // 圧縮方式
+            let lang_end = rest.iter().position(|b| *b == 0)?;
+            let rest = &rest[lang_end + 1..];
+            let translated_end = rest.iter().position(|b| *b == 0)?;
+            String::from_utf8_lossy(&rest[translated_end + 1..]).to_string()
+        }
+        _ => return None,
+    };
+    Some(TextChunk { keyword, text })
+}
+
+/// テキストチャンクだけを読む。画像データ (IDAT) は読み飛ばす
+pub fn read_text_chunks(path: &Path) -> io::Result<Vec<TextChunk>> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut r = BufReader::new(file);
+    check_signature(&mut r)?;
+
+    let mut chunks = Vec::new();
+    while let Some((len, kind)) = read_chunk_header(&mut r, file_len)? {
+        if &kind == b"tEXt" || &kind == b"iTXt" {
+            let mut data = vec![0u8; len as usize];
+            r.read_exact(&mut data)?;
+            r.seek(SeekFrom::Current(4))?; // CRC
+            chunks.extend(parse_text_chunk(&kind, &data));
+        } else {
+            r.seek(SeekFrom::Current(len as i64 + 4))?;
+        }
+        if &kind == b"IEND" {
+            break;
+        }
+    }
+    Ok(chunks)
+}
+
+fn crc_table() -> &'static [u32; 256] {
+    static TABLE: OnceLock<[u32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        table
+    })
+}
+
+/// PNG のチャンクの CRC (チャンクの種類 + データ に対する CRC-32)
+pub fn crc32(parts: &[&[u8]]) -> u32 {
+    let table = crc_table();
+    let mut c = 0xffff_ffffu32;
+    for part in parts {
+        for b in *part {
+            c = table[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8);
+        }
+    }
+    c ^ 0xffff_ffff
+}
+
+fn write_chunk(w: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc32(&[kind, data]).to_be_bytes())
+}
+
+/// 非圧縮の iTXt チャンクを IEND の直前に追加する。
+/// 他のチャンク (画像データを含む) はバイト列のままコピーするので再エンコードはしない。
+/// 途中で失敗しても元のファイルが壊れないよう、一時ファイルに書いてから置き換える
+pub fn append_itxt(path: &Path, keyword: &str, text: &str) -> io::Result<()> {
+    let mut data = Vec::with_capacity(keyword.len() + text.len() + 5);
+    data.extend_from_slice(keyword.as_bytes());
+    data.extend_from_slice(&[0, 0, 0, 0, 0]); // 区切り, 非圧縮, 圧縮方式, 言語タグ, 翻訳キーワード
+    data.extend_from_slice(text.as_bytes());
+
+    let part = path.with_extension("png.part");
+    let result = (|| {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut r = BufReader::new(file);
+        check_signature(&mut r)?;
+        let mut w = BufWriter::new(File::create(&part)?);
+        w.write_all(&SIGNATURE)?;
+
+        let mut buf = Vec::new();
+        loop {
+            let Some((len, kind)) = read_chunk_header(&mut r, file_len)? else {
+                return Err(invalid("Missing IEND chunk"));
+            };
+            if &kind == b"IEND" {
+                write_chunk(&mut w, b"iTXt", &data)?;
+            }
+            buf.resize(len as usize + 4, 0);
+            r.read_exact(&mut buf)?;
+            w.write_all(&len.to_be_bytes())?;
+            w.write_all(&kind)?;
+            w.write_all(&buf)?;
+            if &kind == b"IEND" {
+                break;
+            }
+        }
+        w.flush()?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => fs::rename(&part, path),
+        Err(e) => {
+            let _ = fs::remove_file(&part);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_with(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+        for (kind, data) in chunks {
+            write_chunk(&mut bytes, kind, data).unwrap();
+        }
+        write_chunk(&mut bytes, b"IEND", &[]).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn appends_and_reads_an_itxt_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.png");
+        fs::write(
+            &path,
+            png_with(&[(b"IHDR", &[0; 13]), (b"tEXt", b"Author\0amgr")]),
+        )
+        .unwrap();
+
+        append_itxt(&path, "Description", "{\"players\":[]}").unwrap();
+        let chunks = read_text_chunks(&path).unwrap();
+        let texts: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.keyword.as_str(), c.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            [("Author", "amgr"), ("Description", "{\"players\":[]}")]
+        );
+    }
+
+    #[test]
+    fn rejects_chunk_lengths_beyond_the_file_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.png");
+        // 書き込み途中で切れたファイルのように、長さだけが大きい tEXt
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend_from_slice(&0xffff_fff0u32.to_be_bytes());
+        bytes.extend_from_slice(b"tEXt");
+        bytes.extend_from_slice(b"Author\0");
+        fs::write(&path, &bytes).unwrap();
+
+        let err = read_text_chunks(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = append_itxt(&path, "Description", "{}").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // 失敗しても元のファイルはそのまま
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+        assert!(!path.with_extension("png.part").exists());
+    }
+}
@@ -54,6 +54,30 @@ async deleteAllLogs() : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async startBackfill() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("start_backfill") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getParserVersion() : Promise<Result<ParserVersion, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_parser_version") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async reparseLogs() : Promise<Result<ReparseResult, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reparse_logs") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getSessions(start: number | null, end: number | null) : Promise<Result<SessionPayload[], string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_sessions", { start, end }) };
@@ -62,6 +86,150 @@ async getSessions(start: number | null, end: number | null) : Promise<Result<Ses
     else return { status: "error", error: e  as any };
 }
 },
+async getSessionScreenshots(start: number, end: number) : Promise<Result<Screenshot[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_session_screenshots", { start, end }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async scanScreenshots() : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("scan_screenshots") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getScreenshotStamping() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_screenshot_stamping") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setScreenshotStamping(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_screenshot_stamping", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getCustomRules() : Promise<Result<CustomRule[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_custom_rules") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async addCustomRule(name: string, pattern: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("add_custom_rule", { name, pattern }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async testCustomRule(pattern: string, line: string) : Promise<Result<CustomRuleTest, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("test_custom_rule", { pattern, line }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async removeCustomRule(id: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("remove_custom_rule", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getLogDir() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_log_dir") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLogDir(dir: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_log_dir", { dir }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listLogDirCandidates() : Promise<Result<string[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_log_dir_candidates") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getArchiveRetention() : Promise<Result<ArchiveRetention, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_archive_retention") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setArchiveRetention(retention: ArchiveRetention) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_archive_retention", { retention }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getWatcherStatus() : Promise<Result<WatcherStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_watcher_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async pauseWatcher() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pause_watcher") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resumeWatcher() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("resume_watcher") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async rescanWatcher() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rescan_watcher") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async switchWatcherFile(path: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("switch_watcher_file", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async login(username: string, password: string) : Promise<Result<LoginResponse, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("login", { username, password }) };
@@ -93,10 +261,16 @@ async verify2fa(code: string, isEmailotp: boolean) : Promise<Result<LoginRespons
 
 export const events = __makeEvents__<{
 logPayload: LogPayload,
-vrcLogEvent: VrcLogEvent
+vrcLogEvent: VrcLogEvent,
+backfillProgress: BackfillProgress,
+watcherStatusChanged: WatcherStatusChanged,
+vrcProcessChanged: VrcProcessChanged
 }>({
 logPayload: "log-payload",
-vrcLogEvent: "vrc-log-event"
+vrcLogEvent: "vrc-log-event",
+backfillProgress: "backfill-progress",
+watcherStatusChanged: "watcher-status-changed",
+vrcProcessChanged: "vrc-process-changed"
 })
 
 /** user-defined constants **/
@@ -105,12 +279,28 @@ vrcLogEvent: "vrc-log-event"
 
 /** user-defined types **/
 
+export type ArchiveRetention = { days: number; maxBytes: number }
+export type AvatarUsage = { avatarName: string; timestamp: number }
+export type BackfillProgress = { total: number; processed: number; currentFile: string | null; inserted: number; done: boolean }
+export type CustomRule = { id: number; name: string; pattern: string }
+export type CustomRuleTest = { fields: Partial<{ [key in string]: string }> | null; shadowedBy: string | null }
+export type InstanceAccessType = "public" | "friendsPlus" | "friends" | "invitePlus" | "invite" | "group" | "groupPlus" | "groupPublic"
+export type InstanceInfo = { worldId: string; name: string; accessType: InstanceAccessType; ownerId: string | null; region: string | null; nonce: string | null; canRequestInvite: boolean; strict: boolean }
 export type Interval = { start: number; end: number }
-export type LogPayload = { event: VrcLogEvent; timestamp: number; hash: number }
+export type LogPayload = { event: VrcLogEvent; timestamp: number; hash: number; instance: InstanceInfo | null; account: string | null }
 export type LoginResponse = { user: string | null; requires2fa: boolean; type2fa: string[] }
-export type PlayerInterval = { name: string; intervals: Interval[]; totalDurationMs: number }
-export type SessionPayload = { worldName: string; instanceId: string; startTime: number; endTime: number; durationMs: number; username: string | null; players: PlayerInterval[] }
-export type VrcLogEvent = { type: "AppStart" } | { type: "AppStop" } | { type: "InvalidAppStop" } | { type: "Login"; data: { username: string; user_id: string } } | { type: "WorldEnter"; data: { world_name: string } } | { type: "InstanceJoin"; data: { world_id: string; instance_id: string } } | { type: "PlayerJoin"; data: { player_name: string; user_id: string } } | { type: "PlayerLeft"; data: { player_name: string; user_id: string } } | { type: "SelfLeft" }
+export type ParserVersion = { current: number; stored: number }
+export type PlayerInterval = { name: string; intervals: Interval[]; totalDurationMs: number; avatars: AvatarUsage[] }
+export type ReparseResult = { files: number; inserted: number; skipped: number }
+export type ResourceUsage = { peakMemoryBytes: number; avgCpuUsage: number; peakThreadCount: number | null; sampleCount: number }
+export type Screenshot = { id: number; path: string; takenAt: number; account: string | null; metadata: ScreenshotMetadata | null }
+export type ScreenshotMetadata = { worldId: string | null; worldName: string | null; instanceId: string | null; author: string | null; players: string[]; stamped: boolean }
+export type SessionPayload = { worldName: string; instanceId: string; instance: InstanceInfo | null; startTime: number; endTime: number; durationMs: number; username: string | null; account: string | null; localPlayer: PlayerInterval | null; players: PlayerInterval[]; videos: VideoPlayback[]; resources: ResourceUsage | null; screenshots: Screenshot[] }
+export type VideoPlayback = { url: string; requestedBy: string | null; timestamp: number }
+export type VrcLogEvent = { type: "AppStart" } | { type: "AppStop" } | { type: "InvalidAppStop" } | { type: "Login"; data: { username: string; user_id: string } } | { type: "WorldEnter"; data: { world_name: string } } | { type: "InstanceJoin"; data: { world_id: string; instance_id: string } } | { type: "PlayerJoin"; data: { player_name: string; user_id: string } } | { type: "PlayerLeft"; data: { player_name: string; user_id: string } } | { type: "SelfLeft" } | { type: "VideoPlay"; data: { url: string; requested_by: string | null } } | { type: "AvatarChange"; data: { player_name: string; avatar_name: string } } | { type: "Screenshot"; data: { path: string } } | { type: "Custom"; data: { rule: string; fields: Partial<{ [key in string]: string }> } }
+export type VrcProcessChanged = { pid: number; running: boolean; timestamp: number }
+export type WatcherStatus = { isAppRunning: boolean; lastSeenTimestamp: number; currentFile: string | null; position: number; fileLength: number; watchedFiles: number; linesRead: number; matchedLines: number; unmatchedLines: number; malformedLines: number; lagSeconds: number; lastError: string | null; paused: boolean; pinnedFile: string | null }
+export type WatcherStatusChanged = WatcherStatus
 
 /** tauri-specta globals **/
 
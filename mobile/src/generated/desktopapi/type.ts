@@ -44,8 +44,59 @@ export type VrcLogEvent =
         player_name: string;
         user_id: string;
       };
+    }
+  | {
+      type: 'VideoPlay';
+      data: {
+        url: string;
+        requested_by: string | null;
+      };
+    }
+  | {
+      type: 'AvatarChange';
+      data: {
+        player_name: string;
+        avatar_name: string;
+      };
+    }
+  | {
+      type: 'Screenshot';
+      data: {
+        path: string;
+      };
+    }
+  | {
+      type: 'Custom';
+      data: {
+        rule: string;
+        fields: Record<string, string>;
+      };
     };
 
+/**
+ * Rust側の InstanceInfo に対応 (instance_id を分解した情報)
+ */
+export type InstanceAccessType =
+  | 'public'
+  | 'friendsPlus'
+  | 'friends'
+  | 'invitePlus'
+  | 'invite'
+  | 'group'
+  | 'groupPlus'
+  | 'groupPublic';
+
+export interface InstanceInfo {
+  worldId: string;
+  name: string;
+  accessType: InstanceAccessType;
+  ownerId: string | null;
+  region: string | null;
+  nonce: string | null;
+  canRequestInvite: boolean;
+  strict: boolean;
+}
+
 /**
  * Rust側の Payload 構造体に対応
  */
@@ -53,6 +104,8 @@ export interface LogPayload {
   event: VrcLogEvent;
   timestamp: number; // Unixタイムスタンプ (ミリ秒)
   hash: number; // ログの一意なハッシュ値
+  instance: InstanceInfo | null; // InstanceJoin の場合のみ
+  account: string | null; // ログを書いたクライアントの user_id
 }
 
 /**
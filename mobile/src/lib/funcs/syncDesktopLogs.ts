@@ -6,6 +6,9 @@ import { logsRepo } from "@/db/repogitories/logs";
 import { LogPayload } from "@/generated/desktopapi/type";
 
 const LAST_SYNC_KEY = "DESKTOP_LOG_LAST_SYNC_TIME";
+const HASH_VERSION_KEY = "DESKTOP_LOG_HASH_VERSION";
+// desktop returns the hash version of the logs in this header (lowercased by axios)
+const HASH_VERSION_HEADER = "x-vrcp-hash-version";
 
 export async function syncDesktopLogs(
   desktopUrl: string,
@@ -29,10 +32,21 @@ export async function syncDesktopLogs(
 
     onProgress?.("Fetching data from desktop...");
 
-    const response = await getLogsFromDesktop(desktopUrl, {
+    let response = await getLogsFromDesktop(desktopUrl, {
       start: startTimestamp
     });
 
+    // The desktop recalculated its hashes (e.g. after an update), so the same events now
+    // have different hashes and would be saved twice. Drop the local copy and fetch everything again.
+    const hashVersion = response.headers[HASH_VERSION_HEADER] as string | undefined;
+    if (hashVersion && hashVersion !== await StorageWrapper.getItemAsync(HASH_VERSION_KEY)) {
+      onProgress?.("Desktop log format changed. Resetting local logs...");
+      await logsRepo.deleteAll();
+      if (startTimestamp !== undefined) {
+        response = await getLogsFromDesktop(desktopUrl, {});
+      }
+    }
+
     const newLogs: LogPayload[] = response.data;
 
     if (newLogs && newLogs.length > 0) {
@@ -40,6 +54,10 @@ export async function syncDesktopLogs(
       await logsRepo.bulkUpsert(newLogs);
     }
 
+    // Saved only after the logs, so a failed reset is retried on the next sync
+    if (hashVersion) {
+      await StorageWrapper.setItemAsync(HASH_VERSION_KEY, hashVersion);
+    }
     await StorageWrapper.setItemAsync(LAST_SYNC_KEY, Date.now().toString());
     onProgress?.(`Success! ${newLogs?.length || 0} logs synced.`);
 